@@ -11,7 +11,8 @@ model_vec!(
     ResponseTypes,
     ResponseType {
         Code -> "code",
-        Token -> "token"
+        Token -> "token",
+        IdToken -> "id_token"
     }
 );
 model_vec!(
@@ -25,6 +26,34 @@ model_vec!(
     }
 );
 
+/// Whether a client can keep a secret (RFC 6749 2.1).
+#[derive(
+    Clone, Copy, Debug, Default, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize,
+)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+#[serde(rename_all = "snake_case")]
+pub enum ClientType {
+    #[default]
+    #[sea_orm(string_value = "confidential")]
+    Confidential,
+    #[sea_orm(string_value = "public")]
+    Public,
+}
+
+/// How a client authenticates to the token endpoint (OpenID Connect Dynamic Client Registration
+/// 2).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize)]
+#[sea_orm(rs_type = "String", db_type = "String(None)")]
+#[serde(rename_all = "snake_case")]
+pub enum TokenEndpointAuthMethod {
+    #[sea_orm(string_value = "client_secret_basic")]
+    ClientSecretBasic,
+    #[sea_orm(string_value = "client_secret_post")]
+    ClientSecretPost,
+    #[sea_orm(string_value = "none")]
+    None,
+}
+
 #[derive(Clone, Debug, PartialEq, DeriveEntityModel, Eq, Serialize, Deserialize)]
 #[sea_orm(table_name = "clients")]
 pub struct Model {
@@ -33,16 +62,45 @@ pub struct Model {
     pub uuid: super::uuid::Uuid,
     pub name: String,
     pub description: Option<String>,
-    pub secret: String,
+    pub secret: Option<String>,
     pub redirect_uris: RedirectUris,
     pub grant_types: GrantTypes,
     pub response_types: ResponseTypes,
     pub scope: Scope,
+    pub trusted: bool,
+    pub require_pkce: bool,
+    pub client_type: ClientType,
+    pub token_endpoint_auth_method: TokenEndpointAuthMethod,
     pub created_at: DateTimeUtc,
     pub updated_at: DateTimeUtc,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
-pub enum Relation {}
+pub enum Relation {
+    #[sea_orm(has_many = "super::authorization_codes::Entity")]
+    AuthorizationCodes,
+    #[sea_orm(has_many = "super::consents::Entity")]
+    Consents,
+    #[sea_orm(has_many = "super::refresh_tokens::Entity")]
+    RefreshTokens,
+}
+
+impl Related<super::authorization_codes::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::AuthorizationCodes.def()
+    }
+}
+
+impl Related<super::consents::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Consents.def()
+    }
+}
+
+impl Related<super::refresh_tokens::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::RefreshTokens.def()
+    }
+}
 
 impl ActiveModelBehavior for ActiveModel {}
@@ -5,15 +5,16 @@ macro_rules! model_vec {
 		pub enum $enum {
 			$(
 				#[serde(rename = $variant_str)]
+				#[field(value = $variant_str)]
 				$variant,
 			)*
 		}
 
-		impl ToString for $enum {
-			fn to_string(&self) -> String {
+		impl std::fmt::Display for $enum {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
 				match self {
 					$(
-						$enum::$variant => $variant_str.to_string(),
+						$enum::$variant => write!(f, "{}", $variant_str),
 					)*
 				}
 			}
@@ -41,13 +42,14 @@ macro_rules! model_vec {
 			}
 		}
 
-		impl ToString for $container {
-			fn to_string(&self) -> String {
-				self.0
+		impl std::fmt::Display for $container {
+			fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+				let joined = self.0
 					.iter()
 					.map(|x| x.to_string())
 					.collect::<Vec<_>>()
-					.join(",")
+					.join(",");
+				write!(f, "{}", joined)
 			}
 		}
 
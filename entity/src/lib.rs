@@ -3,7 +3,13 @@
 pub(crate) mod fields;
 pub mod prelude;
 
+pub mod authorization_codes;
 pub mod clients;
+pub mod consents;
+pub mod keys;
+pub mod refresh_tokens;
+pub mod sessions;
+pub mod users;
 
 pub mod uuid {
     use std::str::FromStr;
@@ -1,3 +1,9 @@
 //! `SeaORM` Entity. Generated by sea-orm-codegen 0.12.6
 
+pub use super::authorization_codes::Entity as AuthorizationCodes;
 pub use super::clients::Entity as Clients;
+pub use super::consents::Entity as Consents;
+pub use super::keys::Entity as Keys;
+pub use super::refresh_tokens::Entity as RefreshTokens;
+pub use super::sessions::Entity as Sessions;
+pub use super::users::Entity as Users;
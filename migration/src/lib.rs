@@ -1,12 +1,46 @@
 pub use sea_orm_migration::prelude::*;
 
 mod m20231118_000001_create_clients;
+mod m20231118_000002_create_authorization_codes;
+mod m20231118_000003_create_keys;
+mod m20231118_000004_add_keys_activates_at;
+mod m20231118_000005_create_users;
+mod m20231118_000006_create_sessions;
+mod m20231118_000007_add_authorization_codes_auth_time;
+mod m20231118_000008_create_consents;
+mod m20231118_000009_add_clients_trusted;
+mod m20231118_000010_add_consents_implicit;
+mod m20231118_000011_add_users_claims;
+mod m20231118_000012_add_clients_require_pkce;
+mod m20231118_000013_add_authorization_codes_code_challenge;
+mod m20231118_000014_add_clients_client_type;
+mod m20231118_000015_create_refresh_tokens;
+mod m20231118_000016_add_authorization_codes_refresh_token_family;
+mod m20231118_000017_hash_authorization_codes;
 
 pub struct Migrator;
 
 #[async_trait::async_trait]
 impl MigratorTrait for Migrator {
     fn migrations() -> Vec<Box<dyn MigrationTrait>> {
-        vec![Box::new(m20231118_000001_create_clients::Migration)]
+        vec![
+            Box::new(m20231118_000001_create_clients::Migration),
+            Box::new(m20231118_000002_create_authorization_codes::Migration),
+            Box::new(m20231118_000003_create_keys::Migration),
+            Box::new(m20231118_000004_add_keys_activates_at::Migration),
+            Box::new(m20231118_000005_create_users::Migration),
+            Box::new(m20231118_000006_create_sessions::Migration),
+            Box::new(m20231118_000007_add_authorization_codes_auth_time::Migration),
+            Box::new(m20231118_000008_create_consents::Migration),
+            Box::new(m20231118_000009_add_clients_trusted::Migration),
+            Box::new(m20231118_000010_add_consents_implicit::Migration),
+            Box::new(m20231118_000011_add_users_claims::Migration),
+            Box::new(m20231118_000012_add_clients_require_pkce::Migration),
+            Box::new(m20231118_000013_add_authorization_codes_code_challenge::Migration),
+            Box::new(m20231118_000014_add_clients_client_type::Migration),
+            Box::new(m20231118_000015_create_refresh_tokens::Migration),
+            Box::new(m20231118_000016_add_authorization_codes_refresh_token_family::Migration),
+            Box::new(m20231118_000017_hash_authorization_codes::Migration),
+        ]
     }
 }
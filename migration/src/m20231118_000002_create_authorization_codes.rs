@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(AuthorizationCode::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(AuthorizationCode::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::Code)
+                            .string()
+                            .not_null()
+                            .unique_key(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::ClientId)
+                            .integer()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::RedirectUri)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(ColumnDef::new(AuthorizationCode::Scope).json().not_null())
+                    .col(ColumnDef::new(AuthorizationCode::Nonce).string())
+                    .col(
+                        ColumnDef::new(AuthorizationCode::Subject)
+                            .string()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::ExpiresAt)
+                            .timestamp_with_time_zone()
+                            .not_null(),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::Consumed)
+                            .boolean()
+                            .not_null()
+                            .default(false),
+                    )
+                    .col(
+                        ColumnDef::new(AuthorizationCode::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_authorization_codes_client_id")
+                            .from(AuthorizationCode::Table, AuthorizationCode::ClientId)
+                            .to(Client::Table, Client::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(AuthorizationCode::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthorizationCode {
+    #[sea_orm(iden = "authorization_codes")]
+    Table,
+    Id,
+    Code,
+    ClientId,
+    RedirectUri,
+    Scope,
+    Nonce,
+    Subject,
+    ExpiresAt,
+    Consumed,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum Client {
+    #[sea_orm(iden = "clients")]
+    Table,
+    Id,
+}
@@ -0,0 +1,53 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only accepts constant defaults when adding a column, so existing keys are
+        // backfilled afterwards: they have been in use since they were created.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SigningKey::Table)
+                    .add_column(
+                        ColumnDef::new(SigningKey::ActivatesAt)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default("1970-01-01T00:00:00+00:00"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(SigningKey::Table)
+                    .value(SigningKey::ActivatesAt, Expr::col(SigningKey::CreatedAt))
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(SigningKey::Table)
+                    .drop_column(SigningKey::ActivatesAt)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum SigningKey {
+    #[sea_orm(iden = "keys")]
+    Table,
+    CreatedAt,
+    ActivatesAt,
+}
@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Codes minted before sessions existed were authorized as soon as they were created.
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorizationCode::Table)
+                    .add_column(
+                        ColumnDef::new(AuthorizationCode::AuthTime)
+                            .timestamp_with_time_zone()
+                            .not_null()
+                            .default("1970-01-01T00:00:00+00:00"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .exec_stmt(
+                Query::update()
+                    .table(AuthorizationCode::Table)
+                    .value(
+                        AuthorizationCode::AuthTime,
+                        Expr::col(AuthorizationCode::CreatedAt),
+                    )
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorizationCode::Table)
+                    .drop_column(AuthorizationCode::AuthTime)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthorizationCode {
+    #[sea_orm(iden = "authorization_codes")]
+    Table,
+    CreatedAt,
+    AuthTime,
+}
@@ -0,0 +1,100 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(Consent::Table)
+                    .if_not_exists()
+                    .col(
+                        ColumnDef::new(Consent::Id)
+                            .integer()
+                            .not_null()
+                            .auto_increment()
+                            .primary_key(),
+                    )
+                    .col(ColumnDef::new(Consent::UserId).integer().not_null())
+                    .col(ColumnDef::new(Consent::ClientId).integer().not_null())
+                    .col(ColumnDef::new(Consent::Scope).json().not_null())
+                    .col(
+                        ColumnDef::new(Consent::CreatedAt)
+                            .timestamp_with_time_zone()
+                            .default(Expr::current_timestamp()),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_consents_user_id")
+                            .from(Consent::Table, Consent::UserId)
+                            .to(User::Table, User::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_consents_client_id")
+                            .from(Consent::Table, Consent::ClientId)
+                            .to(Client::Table, Client::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .table(Consent::Table)
+                    .name("idx_consents_user_id_client_id_scope")
+                    .col(Consent::UserId)
+                    .col(Consent::ClientId)
+                    .col(Consent::Scope)
+                    .unique()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .table(Consent::Table)
+                    .name("idx_consents_user_id_client_id_scope")
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .drop_table(Table::drop().table(Consent::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Consent {
+    #[sea_orm(iden = "consents")]
+    Table,
+    Id,
+    UserId,
+    ClientId,
+    Scope,
+    CreatedAt,
+}
+
+#[derive(DeriveIden)]
+enum User {
+    #[sea_orm(iden = "users")]
+    Table,
+    Id,
+}
+
+#[derive(DeriveIden)]
+enum Client {
+    #[sea_orm(iden = "clients")]
+    Table,
+    Id,
+}
@@ -0,0 +1,76 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The standard claims of OIDC Core 5.1 that users did not have a column for yet.
+fn columns() -> Vec<ColumnDef> {
+    vec![
+        ColumnDef::new(User::MiddleName).string().to_owned(),
+        ColumnDef::new(User::Nickname).string().to_owned(),
+        ColumnDef::new(User::Profile).string().to_owned(),
+        ColumnDef::new(User::Picture).string().to_owned(),
+        ColumnDef::new(User::Website).string().to_owned(),
+        ColumnDef::new(User::Gender).string().to_owned(),
+        ColumnDef::new(User::Birthdate).string().to_owned(),
+        ColumnDef::new(User::Zoneinfo).string().to_owned(),
+        ColumnDef::new(User::Locale).string().to_owned(),
+        ColumnDef::new(User::PhoneNumber).string().to_owned(),
+        ColumnDef::new(User::PhoneNumberVerified)
+            .boolean()
+            .not_null()
+            .default(false)
+            .to_owned(),
+        ColumnDef::new(User::Address).json().to_owned(),
+    ]
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // SQLite only alters one column per statement.
+        for mut column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .add_column(&mut column)
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        for column in columns() {
+            manager
+                .alter_table(
+                    Table::alter()
+                        .table(User::Table)
+                        .drop_column(Alias::new(column.get_column_name()))
+                        .to_owned(),
+                )
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum User {
+    #[sea_orm(iden = "users")]
+    Table,
+    MiddleName,
+    Nickname,
+    Profile,
+    Picture,
+    Website,
+    Gender,
+    Birthdate,
+    Zoneinfo,
+    Locale,
+    PhoneNumber,
+    PhoneNumberVerified,
+    Address,
+}
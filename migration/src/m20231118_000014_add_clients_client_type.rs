@@ -0,0 +1,156 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// The columns kept when the table is rebuilt, in both directions.
+const KEPT: &str = "id, uuid, name, description, secret, redirect_uris, grant_types, \
+    response_types, scope, trusted, require_pkce, created_at, updated_at";
+
+/// The new clients table: with a nullable secret and the client type columns after this
+/// migration (`public_clients`), or as it was before.
+fn create_clients(public_clients: bool) -> TableCreateStatement {
+    let mut secret = ColumnDef::new(RegisteredClient::Secret);
+    secret.string();
+    if !public_clients {
+        secret.not_null();
+    }
+
+    let mut create = Table::create();
+    create
+        .table(Alias::new("clients_new"))
+        .col(
+            ColumnDef::new(RegisteredClient::Id)
+                .integer()
+                .not_null()
+                .auto_increment()
+                .primary_key(),
+        )
+        .col(ColumnDef::new(RegisteredClient::Uuid).string().not_null())
+        .col(ColumnDef::new(RegisteredClient::Name).string().not_null())
+        .col(ColumnDef::new(RegisteredClient::Description).string())
+        .col(&mut secret)
+        .col(
+            ColumnDef::new(RegisteredClient::RedirectUris)
+                .json()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(RegisteredClient::GrantTypes)
+                .json()
+                .not_null(),
+        )
+        .col(
+            ColumnDef::new(RegisteredClient::ResponseTypes)
+                .json()
+                .not_null(),
+        )
+        .col(ColumnDef::new(RegisteredClient::Scope).json().not_null())
+        .col(
+            ColumnDef::new(RegisteredClient::Trusted)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .col(
+            ColumnDef::new(RegisteredClient::RequirePkce)
+                .boolean()
+                .not_null()
+                .default(false),
+        )
+        .col(
+            ColumnDef::new(RegisteredClient::CreatedAt)
+                .timestamp_with_time_zone()
+                .default(Expr::current_timestamp()),
+        )
+        .col(
+            ColumnDef::new(RegisteredClient::UpdatedAt)
+                .timestamp_with_time_zone()
+                .default(Expr::current_timestamp()),
+        );
+    if public_clients {
+        create
+            .col(
+                ColumnDef::new(RegisteredClient::ClientType)
+                    .string()
+                    .not_null()
+                    .default("confidential"),
+            )
+            .col(
+                ColumnDef::new(RegisteredClient::TokenEndpointAuthMethod)
+                    .string()
+                    .not_null()
+                    .default("client_secret_basic"),
+            );
+    }
+    create.to_owned()
+}
+
+/// Replaces the clients table by a new one, copying the kept columns over. SQLite cannot alter a column,
+/// so the table is rebuilt as described in https://www.sqlite.org/lang_altertable.html. Foreign
+/// keys are disabled meanwhile, or dropping the table would cascade to the codes and consents of
+/// every client. Everything runs as a single batch, so that it all happens on one connection.
+async fn rebuild_clients(manager: &SchemaManager<'_>, public_clients: bool) -> Result<(), DbErr> {
+    let index = Index::create()
+        .table(RegisteredClient::Table)
+        .name("idx_clients_uuid")
+        .col(RegisteredClient::Uuid)
+        .to_owned();
+    let script = [
+        "PRAGMA foreign_keys = OFF".to_string(),
+        "BEGIN".to_string(),
+        create_clients(public_clients).to_string(SqliteQueryBuilder),
+        format!("INSERT INTO clients_new ({KEPT}) SELECT {KEPT} FROM clients"),
+        "DROP TABLE clients".to_string(),
+        "ALTER TABLE clients_new RENAME TO clients".to_string(),
+        index.to_string(SqliteQueryBuilder),
+        "PRAGMA foreign_key_check".to_string(),
+        "COMMIT".to_string(),
+        "PRAGMA foreign_keys = ON".to_string(),
+    ]
+    .join(";\n");
+
+    manager
+        .get_connection()
+        .execute_unprepared(&script)
+        .await
+        .map(|_| ())
+}
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Every client registered so far has a secret.
+        rebuild_clients(manager, true).await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Public clients cannot be represented without a secret.
+        manager
+            .get_connection()
+            .execute_unprepared("DELETE FROM clients WHERE secret IS NULL")
+            .await?;
+        rebuild_clients(manager, false).await
+    }
+}
+
+#[derive(DeriveIden)]
+enum RegisteredClient {
+    #[sea_orm(iden = "clients")]
+    Table,
+    Id,
+    Uuid,
+    Name,
+    Description,
+    Secret,
+    RedirectUris,
+    GrantTypes,
+    ResponseTypes,
+    Scope,
+    Trusted,
+    RequirePkce,
+    ClientType,
+    TokenEndpointAuthMethod,
+    CreatedAt,
+    UpdatedAt,
+}
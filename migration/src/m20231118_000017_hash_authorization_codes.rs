@@ -0,0 +1,55 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // Outstanding codes are stored in the clear, and could not be redeemed once codes are
+        // looked up by hash anyway.
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(AuthorizationCode::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorizationCode::Table)
+                    .rename_column(AuthorizationCode::Code, AuthorizationCode::CodeHash)
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .exec_stmt(
+                Query::delete()
+                    .from_table(AuthorizationCode::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AuthorizationCode::Table)
+                    .rename_column(AuthorizationCode::CodeHash, AuthorizationCode::Code)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum AuthorizationCode {
+    #[sea_orm(iden = "authorization_codes")]
+    Table,
+    Code,
+    CodeHash,
+}
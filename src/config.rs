@@ -0,0 +1,73 @@
+use std::env;
+
+use crate::oidc::scope::ScopePolicy;
+
+#[derive(Clone)]
+pub struct Config {
+    /// Issuer identifier of this provider, used as the `iss` claim of every token we sign.
+    pub issuer: String,
+
+    /// Lifetime of issued access and ID tokens, in seconds.
+    pub token_lifetime: i64,
+
+    /// Lifetime of refresh tokens, in seconds. Every refresh issues a new token with a full
+    /// lifetime.
+    pub refresh_token_lifetime: i64,
+
+    /// Lifetime of authorization codes, in seconds.
+    pub code_lifetime: i64,
+
+    /// How long a session lasts without the end user coming back to the provider, in seconds.
+    pub session_idle_timeout: i64,
+
+    /// How long a session lasts at most after the end user signed in, in seconds, however active
+    /// they are.
+    pub session_lifetime: i64,
+
+    /// How often a new signing key is generated, in days.
+    pub key_rotation_interval: i64,
+
+    /// How long a new signing key is published before tokens get signed with it, in seconds, so
+    /// that relying parties caching our JWKS pick it up in time.
+    pub key_publication_delay: i64,
+
+    /// What to do with requested scope values a client has not registered: `drop` them from the
+    /// grant or `reject` the request.
+    pub scope_policy: ScopePolicy,
+
+    /// Whether the resource owner password credentials grant is available at all. Clients still
+    /// have to register for it.
+    pub password_grant: bool,
+}
+
+/// Reads `name` from the environment, or falls back to `default` when it is not set. A value
+/// that is set but does not parse stops the server: running with a silently different setting
+/// than the one asked for is worse than not starting.
+fn var_or<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .unwrap_or_else(|_| panic!("invalid value for {name}: {value:?}")),
+        Err(env::VarError::NotPresent) => default,
+        Err(env::VarError::NotUnicode(value)) => {
+            panic!("invalid value for {name}: {value:?}")
+        }
+    }
+}
+
+impl Config {
+    pub fn from_env() -> Self {
+        Config {
+            issuer: var_or("ISSUER", "http://localhost:8000".to_string()),
+            token_lifetime: var_or("TOKEN_LIFETIME", 3600),
+            refresh_token_lifetime: var_or("REFRESH_TOKEN_LIFETIME", 30 * 86400),
+            code_lifetime: var_or("CODE_LIFETIME", 60),
+            session_idle_timeout: var_or("SESSION_IDLE_TIMEOUT", 86400),
+            session_lifetime: var_or("SESSION_LIFETIME", 7 * 86400),
+            key_rotation_interval: var_or("KEY_ROTATION_DAYS", 30),
+            key_publication_delay: var_or("KEY_PUBLICATION_DELAY", 86400),
+            scope_policy: var_or("SCOPE_POLICY", ScopePolicy::Drop),
+            password_grant: var_or("PASSWORD_GRANT", false),
+        }
+    }
+}
@@ -1,17 +1,23 @@
 #[macro_use]
 extern crate rocket;
 
+mod config;
 mod db;
 mod oidc;
+mod password;
 mod rest;
 
 use std::{fs, io};
 
+use rocket_dyn_templates::Template;
 use tracing::Level;
 use tracing_subscriber::fmt::format::FmtSpan;
 
 pub struct App {
     seaorm_pool: sea_orm::DatabaseConnection,
+    config: config::Config,
+    keys: oidc::keys::Keyring,
+    password_throttle: oidc::throttle::Throttle,
 }
 
 const LOG_PATH: &str = "development.log";
@@ -19,7 +25,6 @@ fn write_to_log() -> impl io::Write {
     let pwd = std::env::current_dir().expect("couldn't get current dir");
     fs::File::options()
         .append(true)
-        .write(true)
         .open(pwd.join(LOG_PATH))
         .expect("failed to create file")
 }
@@ -40,6 +45,20 @@ fn initialize_tracing() {
 async fn rocket() -> _ {
     initialize_tracing();
 
+    let seaorm_pool = db::get_seaorm_pool().await.unwrap();
+    let config = config::Config::from_env();
+    let keys = oidc::keys::Keyring::load(&seaorm_pool, &config)
+        .await
+        .unwrap();
+    rocket::tokio::spawn(
+        keys.clone()
+            .rotate_periodically(seaorm_pool.clone(), config.clone()),
+    );
+    rocket::tokio::spawn(oidc::session::purge_periodically(
+        seaorm_pool.clone(),
+        config.clone(),
+    ));
+
     rocket::build()
         .mount(
             "/authorize",
@@ -48,6 +67,17 @@ async fn rocket() -> _ {
                 oidc::authorize::authorize_post
             ],
         )
+        .mount("/login", routes![oidc::login::login])
+        .mount("/consent", routes![oidc::consent::consent])
+        .mount("/token", routes![oidc::token::token])
+        .mount(
+            "/userinfo",
+            routes![oidc::userinfo::userinfo_get, oidc::userinfo::userinfo_post],
+        )
+        .mount(
+            "/.well-known",
+            routes![oidc::keys::jwks, oidc::discovery::openid_configuration],
+        )
         .mount(
             "/clients",
             routes![
@@ -56,7 +86,12 @@ async fn rocket() -> _ {
                 rest::clients::rotate_client_secret
             ],
         )
+        .mount("/users", routes![rest::users::create_user])
+        .attach(Template::fairing())
         .manage(App {
-            seaorm_pool: db::get_seaorm_pool().await.unwrap(),
+            seaorm_pool,
+            config,
+            keys,
+            password_throttle: Default::default(),
         })
 }
@@ -1,12 +1,54 @@
-use entity::clients::{Entity as Client, ResponseType};
-use rocket::{form::Form, http::Status, State};
+use std::str::FromStr;
+
+use chrono::Utc;
+use entity::{
+    clients::{self, ClientType, Entity as Client, GrantType, ResponseType, ResponseTypes, Scope},
+    users,
+    uuid::Uuid,
+};
+use rocket::{
+    form::Form,
+    http::{RawStr, Status},
+    State,
+};
+use rocket_dyn_templates::Template;
 use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use url::Url;
 
 use crate::App;
 
-use super::OidcError;
+use super::{
+    access_token::AccessToken,
+    code, consent,
+    csrf::CsrfToken,
+    id_token::IdToken,
+    login,
+    pkce::Challenge,
+    scope::{self, ScopePolicy},
+    session::Session,
+    signing, userinfo, OidcError,
+};
+
+mod response;
+
+pub use response::{AuthorizationResponse, ResponseMode, RESPONSE_MODES};
 
-#[derive(Debug, FromFormField)]
+/// The combinations of response types the authorization endpoint knows how to answer: the
+/// authorization code flow, the implicit flow and the hybrid flow (OIDC Core 3).
+pub const RESPONSE_TYPES: &[&[ResponseType]] = &[
+    &[ResponseType::Code],
+    &[ResponseType::IdToken],
+    &[ResponseType::IdToken, ResponseType::Token],
+    &[ResponseType::Code, ResponseType::IdToken],
+    &[ResponseType::Code, ResponseType::Token],
+    &[
+        ResponseType::Code,
+        ResponseType::IdToken,
+        ResponseType::Token,
+    ],
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Prompt {
     /// The Authorization Server MUST NOT display any authentication or consent user interface
     /// pages. An error is returned if the End-User is not already authenticated or the Client does
@@ -33,15 +75,89 @@ pub enum Prompt {
     SelectAccount,
 }
 
+impl Prompt {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Prompt::None => "none",
+            Prompt::Login => "login",
+            Prompt::Consent => "consent",
+            Prompt::SelectAccount => "select_account",
+        }
+    }
+}
+
+impl FromStr for Prompt {
+    type Err = OidcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Prompt::None),
+            "login" => Ok(Prompt::Login),
+            "consent" => Ok(Prompt::Consent),
+            "select_account" => Ok(Prompt::SelectAccount),
+            _ => Err(OidcError::InvalidRequest("unknown prompt value")),
+        }
+    }
+}
+
+/// The set of values of the `prompt` parameter.
+#[derive(Debug, Default)]
+pub struct Prompts(Vec<Prompt>);
+
+impl Prompts {
+    pub fn contains(&self, prompt: Prompt) -> bool {
+        self.0.contains(&prompt)
+    }
+
+    pub fn without(&self, prompt: Prompt) -> Prompts {
+        Prompts(self.0.iter().copied().filter(|p| *p != prompt).collect())
+    }
+
+    pub fn with(&self, prompt: Prompt) -> Prompts {
+        let mut prompts = self.without(prompt);
+        prompts.0.push(prompt);
+        prompts
+    }
+}
+
+impl std::fmt::Display for Prompts {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let values = self.0.iter().map(Prompt::as_str).collect::<Vec<_>>();
+        write!(f, "{}", values.join(" "))
+    }
+}
+
+impl FromStr for Prompts {
+    type Err = OidcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut prompts = Vec::new();
+        for value in s.split_whitespace() {
+            let prompt = value.parse()?;
+            if !prompts.contains(&prompt) {
+                prompts.push(prompt);
+            }
+        }
+        if prompts.contains(&Prompt::None) && prompts.len() > 1 {
+            return Err(OidcError::InvalidRequest(
+                "prompt=none cannot be combined with other values",
+            ));
+        }
+        Ok(Prompts(prompts))
+    }
+}
+
 #[derive(FromForm)]
 pub struct AuthorizePayload {
     /// OAuth 2.0 Response Type value that determines the authorization processing flow to be used,
     /// including what parameters are returned from the endpoints used. When using the
-    /// authorization code flow, this value is `code`.
-    response_type: ResponseType,
+    /// authorization code flow, this value is `code`. A space delimited set of values for the
+    /// implicit and hybrid flows. Kept as a raw, optional string so that missing and unknown values
+    /// can be reported to the client as `invalid_request` and `unsupported_response_type`.
+    response_type: Option<String>,
 
     /// OAuth 2.0 Client Identifier valid at the Authorization Server.
-    client_id: String,
+    client_id: Option<String>,
 
     /// Redirection URI to which the response will be sent. This URI MUST exactly match one of the
     /// Redirection URI values for the Client pre-registered at the OpenID Provider, with the
@@ -51,55 +167,244 @@ pub struct AuthorizePayload {
     /// of OAuth 2.0, and provided the OP allows the use of http Redirection URIs in this case. The
     /// Redirection URI MAY use an alternate scheme, such as one that is intended to identify a
     /// callback into a native application.
-    redirect_uri: String,
+    redirect_uri: Option<String>,
 
     /// OpenID Connect requests MUST contain the `openid` scope value. If the `openid` scope value
     /// is not present, the behavior is entirely unspecified. Other scope values MAY be present.
     /// Scope values used that are not understood by an implementation SHOULD be ignored. See
     /// Sections 5.4 and 11 for additional scope values defined by this specification.
-    scope: String,
+    scope: Option<String>,
 
     /// Opaque value used to maintain state between the request and the callback. Typically,
     /// Cross-Site Request Forgery (CSRF, XSRF) mitigation is done by cryptographically binding the
     /// value of this parameter with a browser cookie.
-    state: String,
+    state: Option<String>,
 
     /// Space delimited, case sensitive list of ASCII string values that specifies whether the
     /// Authorization Server prompts the End-User for reauthentication and consent.
-    prompt: Option<Prompt>,
+    prompt: Option<String>,
 
     /// String value used to associate a Client session with an ID Token, and to mitigate replay
     /// attacks. The value is passed through unmodified from the Authentication Request to the ID
     /// Token. Sufficient entropy MUST be present in the nonce values used to prevent attackers
     /// from guessing values. For implementation notes, see Section 15.5.2.
     nonce: Option<String>,
+
+    /// Hint to the Authorization Server about the login identifier the End-User might use to log
+    /// in (if necessary). When the end user is signed in with another account, they are asked
+    /// which one to use.
+    login_hint: Option<String>,
+
+    /// Maximum Authentication Age. Specifies the allowable elapsed time in seconds since the last
+    /// time the End-User was actively authenticated by the OP. If the elapsed time is greater
+    /// than this value, the OP MUST attempt to actively re-authenticate the End-User. Kept as a
+    /// raw string, like `prompt`, so that a malformed value is refused rather than ignored.
+    max_age: Option<String>,
+
+    /// PKCE code challenge (RFC 7636 4.3), derived from a code verifier the client will present
+    /// when redeeming the code.
+    code_challenge: Option<String>,
+
+    /// How the code challenge was derived from the verifier: `S256` or `plain` (the default).
+    code_challenge_method: Option<String>,
+
+    /// Informs the Authorization Server of the mechanism to be used for returning Authorization
+    /// Response parameters from the Authorization Endpoint. This use of this parameter is NOT
+    /// RECOMMENDED with a value that specifies the same Response Mode as the default Response Mode
+    /// for the Response Type used.
+    response_mode: Option<String>,
+}
+
+/// Something the end user goes through at the provider before their authorization request is
+/// resumed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Interaction {
+    /// Signing in, which satisfies `prompt=login`, `prompt=select_account`, `login_hint` and
+    /// `max_age`.
+    Login,
+
+    /// Consenting to the requested scope, which satisfies `prompt=consent`.
+    Consent,
+
+    /// Choosing to go on with the account they are signed in with, which satisfies
+    /// `prompt=select_account` and `login_hint`.
+    KeepAccount,
+
+    /// Choosing to sign in with another account, which the login page then takes care of.
+    SwitchAccount,
 }
 
 impl AuthorizePayload {
-    pub fn client_id(&self) -> &str {
-        &self.client_id
+    pub fn client_id(&self) -> Option<&str> {
+        self.client_id.as_deref()
+    }
+
+    /// The set of response types requested, provided it is one of [`RESPONSE_TYPES`]. The order of
+    /// the values does not matter.
+    pub fn response_type(&self) -> Result<ResponseTypes, OidcError> {
+        let Some(response_type) = &self.response_type else {
+            return Err(OidcError::InvalidRequest("missing response_type"));
+        };
+        let mut values = Vec::new();
+        for value in response_type.split_whitespace() {
+            let value =
+                ResponseType::from_str(value).map_err(|_| OidcError::UnsupportedResponseType)?;
+            if !values.contains(&value) {
+                values.push(value);
+            }
+        }
+        let supported = RESPONSE_TYPES.iter().any(|types| {
+            types.len() == values.len() && types.iter().all(|value| values.contains(value))
+        });
+        if !supported {
+            return Err(OidcError::UnsupportedResponseType);
+        }
+        Ok(ResponseTypes(values))
+    }
+
+    fn requested_response_mode(&self) -> Result<Option<ResponseMode>, OidcError> {
+        self.response_mode.as_deref().map(str::parse).transpose()
+    }
+
+    /// How the response is delivered to the client: the mode it asked for, or the default for the
+    /// response type. Tokens are never put in the query, where they could leak through logs or
+    /// the `Referer` header (OAuth 2.0 Multiple Response Type Encoding Practices 5), signed or
+    /// not.
+    pub fn response_mode(&self) -> Result<ResponseMode, OidcError> {
+        let default = ResponseMode::default_for(&self.response_type()?);
+        match self.requested_response_mode()? {
+            Some(mode) if mode.resolve(default).in_query() && !default.in_query() => Err(
+                OidcError::InvalidRequest("response_mode cannot put tokens in the query"),
+            ),
+            Some(mode) => Ok(mode.resolve(default)),
+            None => Ok(default),
+        }
+    }
+
+    /// How an error response is delivered to the client. Since errors carry no token, the mode
+    /// the client asked for is used whenever it is a valid one, even if the request is not.
+    pub fn error_response_mode(&self) -> ResponseMode {
+        let default = self
+            .response_type()
+            .map_or(ResponseMode::Query, |response_type| {
+                ResponseMode::default_for(&response_type)
+            });
+        match self.requested_response_mode() {
+            Ok(Some(mode)) => mode.resolve(default),
+            _ => default,
+        }
     }
 
-    pub fn response_type(&self) -> &ResponseType {
-        &self.response_type
+    pub fn state(&self) -> Option<String> {
+        self.state.clone()
+    }
+
+    /// The scope the request can be granted: what was asked for, narrowed down to what `client`
+    /// registered according to `policy`. OpenID Connect requests must include `openid`.
+    pub fn scope(&self, client: &clients::Model, policy: ScopePolicy) -> Result<Scope, OidcError> {
+        let Some(scope) = &self.scope else {
+            return Err(OidcError::InvalidRequest("missing scope"));
+        };
+        let scope = scope::restrict(scope::parse(scope)?, &client.scope, policy)?;
+        if !scope.0.iter().any(|value| value == "openid") {
+            return Err(OidcError::InvalidScope);
+        }
+        Ok(scope)
+    }
+
+    pub fn max_age(&self) -> Result<Option<u32>, OidcError> {
+        self.max_age
+            .as_deref()
+            .map(|max_age| {
+                max_age.parse().map_err(|_| {
+                    OidcError::InvalidRequest("max_age must be a non-negative number of seconds")
+                })
+            })
+            .transpose()
+    }
+
+    pub fn prompts(&self) -> Result<Prompts, OidcError> {
+        match &self.prompt {
+            Some(prompt) => prompt.parse(),
+            None => Ok(Prompts::default()),
+        }
+    }
+
+    /// The request as a query string, for the end user to pick it up again after `interaction`.
+    /// Whatever the interaction satisfied is dropped from the request, so that it is not asked
+    /// for a second time.
+    pub fn resume(&self, interaction: Interaction) -> String {
+        let prompts = self.prompts().unwrap_or_default();
+        let max_age = self.max_age.clone();
+        let (prompts, max_age, login_hint) = match interaction {
+            Interaction::Login => (
+                prompts
+                    .without(Prompt::Login)
+                    .without(Prompt::SelectAccount),
+                None,
+                None,
+            ),
+            Interaction::Consent => (
+                prompts.without(Prompt::Consent),
+                max_age,
+                self.login_hint.clone(),
+            ),
+            Interaction::KeepAccount => (prompts.without(Prompt::SelectAccount), max_age, None),
+            Interaction::SwitchAccount => (
+                prompts.without(Prompt::SelectAccount).with(Prompt::Login),
+                max_age,
+                None,
+            ),
+        };
+        let prompt = Some(prompts.to_string()).filter(|prompt| !prompt.is_empty());
+
+        let params = [
+            ("response_type", &self.response_type),
+            ("client_id", &self.client_id),
+            ("redirect_uri", &self.redirect_uri),
+            ("scope", &self.scope),
+            ("state", &self.state),
+            ("prompt", &prompt),
+            ("nonce", &self.nonce),
+            ("login_hint", &login_hint),
+            ("max_age", &max_age),
+            ("code_challenge", &self.code_challenge),
+            ("code_challenge_method", &self.code_challenge_method),
+            ("response_mode", &self.response_mode),
+        ];
+        let mut query = url::form_urlencoded::Serializer::new(String::new());
+        for (name, value) in params {
+            if let Some(value) = value {
+                query.append_pair(name, value);
+            }
+        }
+        query.finish()
     }
 }
 
-impl ToString for AuthorizePayload {
-    fn to_string(&self) -> String {
-        format!(
-            "response_type={:?}, client_id={}, redirect_uri={}, scope={}, state={}, prompt={:?}, nonce={:?}",
+impl std::fmt::Display for AuthorizePayload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "response_type={:?}, client_id={:?}, redirect_uri={:?}, scope={:?}, state={:?}, prompt={:?}, nonce={:?}, login_hint={:?}, max_age={:?}, code_challenge={:?}, code_challenge_method={:?}, response_mode={:?}",
             self.response_type,
             self.client_id,
             self.redirect_uri,
             self.scope,
             self.state,
-			self.prompt,
-            self.nonce
+            self.prompt,
+            self.nonce,
+            self.login_hint,
+            self.max_age,
+            self.code_challenge,
+            self.code_challenge_method,
+            self.response_mode
         )
     }
 }
 
+/// Errors raised before the client and its redirection URI have been validated. OIDC Core 3.1.2.6
+/// forbids redirecting these back to the client, so they are shown to the user instead.
 #[derive(Debug, thiserror::Error)]
 enum Error {
     #[error("Database error: {0}")]
@@ -107,55 +412,374 @@ enum Error {
 
     #[error("OIDC error: {0}")]
     Oidc(#[from] OidcError),
+
+    #[error("Signing error: {0}")]
+    Signing(#[from] signing::Error),
 }
 
 impl From<Error> for (Status, String) {
     fn from(err: Error) -> Self {
         match err {
-            Error::Db(e) => (Status::InternalServerError, e.to_string()),
             Error::Oidc(e) => (Status::BadRequest, e.to_string()),
+            e => (Status::InternalServerError, e.to_string()),
         }
     }
 }
 
-async fn handle_authorize(
-    app: &State<App>,
+/// Looks up the client the request is for and checks that the redirection URI is one it has
+/// registered, which is what makes it safe to redirect anything back to it.
+async fn validate_client(
+    app: &App,
+    payload: &AuthorizePayload,
+) -> Result<(clients::Model, Url), Error> {
+    let Some(client_id) = payload.client_id() else {
+        return Err(OidcError::InvalidRequest("missing client_id").into());
+    };
+    let client_uuid = Uuid::from_str(client_id).map_err(|_| OidcError::InvalidClient)?;
+    let q = Client::find().filter(clients::Column::Uuid.eq(client_uuid));
+    let Some(client) = q.one(&app.seaorm_pool).await? else {
+        return Err(OidcError::InvalidClient.into());
+    };
+
+    // Redirection URIs are compared with simple string comparison (RFC 3986 6.2.1), as required
+    // by OIDC Core 3.1.2.1.
+    let Some(redirect_uri) = &payload.redirect_uri else {
+        return Err(OidcError::InvalidRequest("missing redirect_uri").into());
+    };
+    if !client.redirect_uris.0.contains(redirect_uri) {
+        return Err(OidcError::InvalidRequest("redirect_uri is not registered").into());
+    }
+    let redirect_uri = Url::parse(redirect_uri)
+        .map_err(|_| OidcError::InvalidRequest("malformed redirect_uri"))?;
+
+    Ok((client, redirect_uri))
+}
+
+/// Parses an authorization request the end user resumes after an interaction, and validates its
+/// client again, since the query string went through the browser.
+pub(crate) async fn parse_resumed(
+    app: &App,
+    query: &str,
+) -> Result<(clients::Model, Url, AuthorizePayload), (Status, String)> {
+    let payload = Form::<AuthorizePayload>::parse_encoded(RawStr::new(query)).map_err(|e| {
+        (
+            Status::BadRequest,
+            format!("Malformed authorization request: {}", e),
+        )
+    })?;
+    let (client, redirect_uri) = validate_client(app, &payload).await?;
+    Ok((client, redirect_uri, payload))
+}
+
+/// Whether the end user has to sign in again, even though they have a session: because the
+/// client asked for it explicitly, or because they authenticated longer than `max_age` ago.
+fn must_reauthenticate(session: &Session, prompts: &Prompts, max_age: Option<u32>) -> bool {
+    if prompts.contains(Prompt::Login) {
+        return true;
+    }
+    let elapsed = (Utc::now() - session.auth_time()).num_seconds();
+    max_age.is_some_and(|max_age| elapsed > i64::from(max_age))
+}
+
+/// What the authorization endpoint answers with: either the authorization response itself, or a
+/// page the end user has to go through first.
+#[derive(Responder)]
+pub enum AuthorizeOutcome {
+    Response(AuthorizationResponse),
+    Page(Template),
+}
+
+async fn authorize(
+    app: &App,
+    client: &clients::Model,
+    redirect_uri: Url,
+    session: Option<Session>,
+    csrf: &CsrfToken,
     payload: AuthorizePayload,
-) -> Result<Option<String>, Error> {
-    if payload.response_type() != &ResponseType::Code {
-        return Err(Error::Oidc(OidcError::UnsupportedResponseType));
+) -> Result<AuthorizeOutcome, Error> {
+    let response_type = payload.response_type()?;
+    let issues_code = response_type.0.contains(&ResponseType::Code);
+    let issues_access_token = response_type.0.contains(&ResponseType::Token);
+    let issues_id_token = response_type.0.contains(&ResponseType::IdToken);
+    // Every value has to be registered. A code has to be exchanged at the token endpoint, which
+    // the client must be allowed to do as well, and tokens returned from the authorization
+    // endpoint make up the implicit grant.
+    let registered = response_type
+        .0
+        .iter()
+        .all(|value| client.response_types.0.contains(value));
+    if !registered
+        || (issues_code && !client.grant_types.0.contains(&GrantType::AuthorizationCode))
+        || ((issues_access_token || issues_id_token)
+            && !client.grant_types.0.contains(&GrantType::Implicit))
+    {
+        return Err(OidcError::UnauthorizedClient.into());
     }
-    let q = Client::find().filter(entity::clients::Column::Uuid.eq(payload.client_id()));
-    let Some(client) = q.one(&app.seaorm_pool).await? else {
-        return Ok(None);
+    // Tokens returned from the authorization endpoint are only protected against replay by the
+    // nonce (OIDC Core 3.2.2.1 and 3.3.2.11).
+    if response_type.0 != [ResponseType::Code] && payload.nonce.is_none() {
+        return Err(OidcError::InvalidRequest("nonce required").into());
+    }
+    let response_mode = payload.response_mode()?;
+    let scope = payload.scope(client, app.config.scope_policy)?;
+    let code_challenge = Challenge::parse(
+        payload.code_challenge.as_deref(),
+        payload.code_challenge_method.as_deref(),
+    )?;
+    // Public clients have nothing else to prove the code was issued to them.
+    let pkce_required = client.require_pkce || client.client_type == ClientType::Public;
+    if code_challenge.is_none() && pkce_required && issues_code {
+        return Err(OidcError::InvalidRequest("code_challenge required").into());
+    }
+    let prompts = payload.prompts()?;
+    let max_age = payload.max_age()?;
+
+    // The end user is signed in, but not recently enough: they have to sign in again, which
+    // cannot happen under prompt=none.
+    let stale = session
+        .as_ref()
+        .is_some_and(|session| must_reauthenticate(session, &prompts, max_age));
+    let session = session.filter(|_| !stale);
+    let user = match &session {
+        Some(session) => {
+            users::Entity::find_by_id(session.user_id())
+                .one(&app.seaorm_pool)
+                .await?
+        }
+        None => None,
+    };
+    let (Some(session), Some(user)) = (session, user) else {
+        if prompts.contains(Prompt::None) && stale {
+            return Err(OidcError::InteractionRequired.into());
+        }
+        if prompts.contains(Prompt::None) {
+            return Err(OidcError::LoginRequired.into());
+        }
+        let resume = payload.resume(Interaction::Login);
+        return Ok(AuthorizeOutcome::Page(login::page(
+            &resume,
+            csrf,
+            payload.login_hint.as_deref(),
+            None,
+        )));
+    };
+
+    // The client expects another account than the one the end user is signed in with, or wants
+    // them to choose.
+    let other_account = payload
+        .login_hint
+        .as_deref()
+        .is_some_and(|hint| hint != user.username);
+    if prompts.contains(Prompt::SelectAccount) || other_account {
+        if prompts.contains(Prompt::None) {
+            return Err(OidcError::AccountSelectionRequired.into());
+        }
+        return Ok(AuthorizeOutcome::Page(login::select_account_page(
+            &payload.resume(Interaction::KeepAccount),
+            &payload.resume(Interaction::SwitchAccount),
+            &user,
+        )));
+    }
+
+    let consented = consent::covers(&app.seaorm_pool, user.id, client.id, &scope).await?;
+    if client.trusted && !consented && !prompts.contains(Prompt::Consent) {
+        // Trusted clients are approved without asking, the grant is still recorded for auditing.
+        consent::grant(&app.seaorm_pool, user.id, client.id, &scope, true).await?;
+    } else if prompts.contains(Prompt::Consent) || !consented {
+        if prompts.contains(Prompt::None) {
+            return Err(OidcError::ConsentRequired.into());
+        }
+        let resume = payload.resume(Interaction::Consent);
+        return Ok(AuthorizeOutcome::Page(consent::page(
+            &resume, csrf, client, &scope,
+        )));
+    }
+
+    let mut response = AuthorizationResponse::new(redirect_uri).mode(response_mode);
+    let subject = user.subject.to_string();
+    let client_id = client.uuid.to_string();
+
+    let code = if issues_code {
+        let authorization = code::Authorization {
+            // Present and registered, as validate_client checked. It is stored as presented, for
+            // the token request to repeat exactly.
+            redirect_uri: payload.redirect_uri.clone().unwrap_or_default(),
+            scope: scope.clone(),
+            nonce: payload.nonce.clone(),
+            subject: subject.clone(),
+            auth_time: session.auth_time(),
+            code_challenge,
+        };
+        let code = code::issue(
+            &app.seaorm_pool,
+            client,
+            authorization,
+            app.config.code_lifetime,
+        )
+        .await?;
+        response = response.param("code", code.clone());
+        Some(code)
+    } else {
+        None
     };
-    Ok(Some(format!(
-        "Hello, world! {}, {}",
-        payload.to_string(),
-        client.uuid
-    )))
+
+    let access_token = if issues_access_token {
+        let access_token = AccessToken::new(
+            &app.config.issuer,
+            &subject,
+            &client_id,
+            &scope.0,
+            app.config.token_lifetime,
+        )
+        .sign(&app.keys.signing_key())?;
+        response = response
+            .param("access_token", access_token.clone())
+            .param("token_type", "Bearer")
+            .param("expires_in", app.config.token_lifetime.to_string());
+        Some(access_token)
+    } else {
+        None
+    };
+
+    if issues_id_token {
+        let mut id_token = IdToken::new(
+            &app.config.issuer,
+            &subject,
+            &client_id,
+            app.config.token_lifetime,
+            session.auth_time(),
+        )
+        .nonce(payload.nonce.clone());
+        if let Some(access_token) = &access_token {
+            id_token = id_token.access_token(access_token);
+        }
+        if let Some(code) = &code {
+            id_token = id_token.code(code);
+        }
+        if access_token.is_none() && code.is_none() {
+            let claims = userinfo::release(&user, scope.0.iter().map(String::as_str));
+            id_token = id_token.claims(claims);
+        }
+        response = response.param("id_token", id_token.sign(&app.keys.signing_key())?);
+    }
+
+    Ok(AuthorizeOutcome::Response(response.state(payload.state)))
+}
+
+async fn handle_authorize(
+    app: &State<App>,
+    session: Option<Session>,
+    csrf: CsrfToken,
+    payload: AuthorizePayload,
+) -> Result<AuthorizeOutcome, Error> {
+    let (client, redirect_uri) = validate_client(app, &payload).await?;
+    let state = payload.state.clone();
+    let mode = payload.error_response_mode();
+
+    let response =
+        match authorize(app, &client, redirect_uri.clone(), session, &csrf, payload).await {
+            Ok(AuthorizeOutcome::Response(response)) => response,
+            Ok(page) => return Ok(page),
+            Err(e) => {
+                let err = match e {
+                    Error::Oidc(e) => e,
+                    e => {
+                        tracing::error!("authorization request failed: {}", e);
+                        OidcError::ServerError
+                    }
+                };
+                AuthorizationResponse::error(redirect_uri, &err)
+                    .mode(mode)
+                    .state(state)
+            }
+        };
+    let response = response.sign(
+        &app.keys.signing_key(),
+        &app.config.issuer,
+        &client.uuid.to_string(),
+    )?;
+    Ok(AuthorizeOutcome::Response(response))
 }
 
 #[get("/?<payload..>")]
 pub async fn authorize_get(
     app: &State<App>,
+    session: Option<Session>,
+    csrf: CsrfToken,
     payload: AuthorizePayload,
-) -> Result<String, (Status, String)> {
-    match handle_authorize(app, payload).await {
-        Ok(Some(result)) => Ok(result),
-        Ok(None) => Err((Status::NotFound, "Not found".to_string())),
-        Err(e) => Err(e.into()),
-    }
+) -> Result<AuthorizeOutcome, (Status, String)> {
+    Ok(handle_authorize(app, session, csrf, payload).await?)
 }
 
 #[post("/", data = "<payload>")]
 pub async fn authorize_post(
     app: &State<App>,
+    session: Option<Session>,
+    csrf: CsrfToken,
     payload: Form<AuthorizePayload>,
-) -> Result<String, (Status, String)> {
-    match handle_authorize(app, payload.into_inner()).await {
-        Ok(Some(result)) => Ok(result),
-        Ok(None) => Err((Status::NotFound, "Not found".to_string())),
-        Err(e) => Err(e.into()),
+) -> Result<AuthorizeOutcome, (Status, String)> {
+    Ok(handle_authorize(app, session, csrf, payload.into_inner()).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prompts_are_a_set() {
+        let prompts: Prompts = "login consent login".parse().unwrap();
+        assert!(prompts.contains(Prompt::Login));
+        assert!(prompts.contains(Prompt::Consent));
+        assert!(!prompts.contains(Prompt::None));
+        assert_eq!(prompts.to_string(), "login consent");
+        assert_eq!(prompts.without(Prompt::Login).to_string(), "consent");
+    }
+
+    #[test]
+    fn prompt_none_stands_alone() {
+        assert!("none".parse::<Prompts>().unwrap().contains(Prompt::None));
+        assert!("none login".parse::<Prompts>().is_err());
+    }
+
+    #[test]
+    fn unknown_prompts_are_refused() {
+        assert!("login bogus".parse::<Prompts>().is_err());
+    }
+
+    fn parse(query: &str) -> AuthorizePayload {
+        Form::<AuthorizePayload>::parse_encoded(RawStr::new(query)).unwrap()
+    }
+
+    #[test]
+    fn missing_response_type_is_an_invalid_request() {
+        let payload = parse("client_id=c&redirect_uri=https%3A%2F%2Fexample.com");
+        assert_eq!(
+            payload.response_type().unwrap_err().code(),
+            "invalid_request"
+        );
+        assert_eq!(payload.error_response_mode(), ResponseMode::Query);
+
+        let payload = parse("response_type=code%20bogus");
+        assert_eq!(
+            payload.response_type().unwrap_err().code(),
+            "unsupported_response_type"
+        );
+    }
+
+    #[test]
+    fn malformed_max_age_is_refused() {
+        assert_eq!(parse("max_age=60").max_age().unwrap(), Some(60));
+        assert_eq!(parse("").max_age().unwrap(), None);
+        assert!(parse("max_age=-1").max_age().is_err());
+        assert!(parse("max_age=soon").max_age().is_err());
+    }
+
+    #[test]
+    fn resume_keeps_only_present_parameters() {
+        let payload = parse("client_id=c&scope=openid&prompt=login%20consent");
+        assert_eq!(
+            payload.resume(Interaction::Login),
+            "client_id=c&scope=openid&prompt=consent"
+        );
     }
 }
@@ -0,0 +1,258 @@
+use std::{collections::BTreeMap, str::FromStr};
+
+use chrono::Utc;
+use entity::clients::{ResponseType, ResponseTypes};
+use rocket::{
+    request::Request,
+    response::{self, Redirect, Responder},
+};
+use rocket_dyn_templates::{context, Template};
+use serde::Serialize;
+use url::Url;
+
+use crate::oidc::{
+    signing::{self, SigningKey},
+    OidcError,
+};
+
+/// How long a JWT secured authorization response is valid. It is meant to be processed right
+/// away, JARM recommends 10 minutes at most.
+const JWT_LIFETIME: i64 = 600;
+
+/// Where the parameters of an authorization response go in the redirection URI.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResponseMode {
+    /// In the query component, the default for the authorization code flow.
+    Query,
+
+    /// In the fragment component, the default as soon as a token is returned from the
+    /// authorization endpoint (OAuth 2.0 Multiple Response Type Encoding Practices 2.1), so that
+    /// it never reaches the client's server.
+    Fragment,
+
+    /// As the fields of a form the end user's browser posts to the redirection URI (OAuth 2.0
+    /// Form Post Response Mode), so that nothing ends up in the browser history or server logs.
+    FormPost,
+
+    /// The JWT secured (JARM) flavour of `query`: the parameters are wrapped in a single
+    /// `response` JWT signed by the provider. Responses are never encrypted, so this mode cannot
+    /// carry tokens either.
+    QueryJwt,
+
+    /// The JWT secured flavour of `fragment`.
+    FragmentJwt,
+
+    /// The JWT secured flavour of `form_post`.
+    FormPostJwt,
+
+    /// The JWT secured flavour of the default mode for the response type.
+    Jwt,
+}
+
+/// The response modes the authorization endpoint can deliver responses in.
+pub const RESPONSE_MODES: &[ResponseMode] = &[
+    ResponseMode::Query,
+    ResponseMode::Fragment,
+    ResponseMode::FormPost,
+    ResponseMode::QueryJwt,
+    ResponseMode::FragmentJwt,
+    ResponseMode::FormPostJwt,
+    ResponseMode::Jwt,
+];
+
+impl ResponseMode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ResponseMode::Query => "query",
+            ResponseMode::Fragment => "fragment",
+            ResponseMode::FormPost => "form_post",
+            ResponseMode::QueryJwt => "query.jwt",
+            ResponseMode::FragmentJwt => "fragment.jwt",
+            ResponseMode::FormPostJwt => "form_post.jwt",
+            ResponseMode::Jwt => "jwt",
+        }
+    }
+
+    /// Replaces `jwt` with the JWT secured flavour of `default` (JARM 2.3.4).
+    pub fn resolve(self, default: ResponseMode) -> Self {
+        match (self, default) {
+            (ResponseMode::Jwt, ResponseMode::Query) => ResponseMode::QueryJwt,
+            (ResponseMode::Jwt, _) => ResponseMode::FragmentJwt,
+            (mode, _) => mode,
+        }
+    }
+
+    /// Whether the parameters end up in the query component of the redirection URI.
+    pub fn in_query(&self) -> bool {
+        matches!(self, ResponseMode::Query | ResponseMode::QueryJwt)
+    }
+
+    fn is_jwt(&self) -> bool {
+        matches!(
+            self,
+            ResponseMode::QueryJwt
+                | ResponseMode::FragmentJwt
+                | ResponseMode::FormPostJwt
+                | ResponseMode::Jwt
+        )
+    }
+
+    /// The mode to use when the request does not ask for one.
+    pub fn default_for(response_type: &ResponseTypes) -> Self {
+        if response_type.0 == [ResponseType::Code] {
+            ResponseMode::Query
+        } else {
+            ResponseMode::Fragment
+        }
+    }
+}
+
+impl std::fmt::Display for ResponseMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for ResponseMode {
+    type Err = OidcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "query" => Ok(ResponseMode::Query),
+            "fragment" => Ok(ResponseMode::Fragment),
+            "form_post" => Ok(ResponseMode::FormPost),
+            "query.jwt" => Ok(ResponseMode::QueryJwt),
+            "fragment.jwt" => Ok(ResponseMode::FragmentJwt),
+            "form_post.jwt" => Ok(ResponseMode::FormPostJwt),
+            "jwt" => Ok(ResponseMode::Jwt),
+            _ => Err(OidcError::InvalidRequest("unsupported response_mode")),
+        }
+    }
+}
+
+/// The claims of a JWT secured authorization response (JARM 2.1): the response parameters, along
+/// with who issued them and for whom.
+#[derive(Debug, Serialize)]
+struct JwtResponse<'a> {
+    iss: &'a str,
+    aud: &'a str,
+    exp: i64,
+    #[serde(flatten)]
+    params: BTreeMap<&'static str, &'a str>,
+}
+
+/// The parameters of an authorization response, along with the (already validated) redirection
+/// URI of the client they are delivered to.
+#[derive(Debug)]
+pub struct AuthorizationResponse {
+    redirect_uri: Url,
+    mode: ResponseMode,
+    params: Vec<(&'static str, String)>,
+}
+
+impl AuthorizationResponse {
+    pub fn new(redirect_uri: Url) -> Self {
+        AuthorizationResponse {
+            redirect_uri,
+            mode: ResponseMode::Query,
+            params: Vec::new(),
+        }
+    }
+
+    /// An error response, as described in RFC 6749 4.1.2.1.
+    pub fn error(redirect_uri: Url, err: &OidcError) -> Self {
+        Self::new(redirect_uri)
+            .param("error", err.code())
+            .param("error_description", err.to_string())
+    }
+
+    pub fn mode(mut self, mode: ResponseMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn param(mut self, name: &'static str, value: impl Into<String>) -> Self {
+        self.params.push((name, value.into()));
+        self
+    }
+
+    /// Wraps the parameters in a `response` JWT signed with `key`, when the response mode asks for
+    /// it. This is the last thing done to a response before it is sent.
+    pub fn sign(
+        self,
+        key: &SigningKey,
+        issuer: &str,
+        client_id: &str,
+    ) -> Result<Self, signing::Error> {
+        if !self.mode.is_jwt() {
+            return Ok(self);
+        }
+        let claims = JwtResponse {
+            iss: issuer,
+            aud: client_id,
+            exp: Utc::now().timestamp() + JWT_LIFETIME,
+            params: self
+                .params
+                .iter()
+                .map(|(name, value)| (*name, value.as_str()))
+                .collect(),
+        };
+        let response = key.sign(&claims)?;
+        Ok(AuthorizationResponse {
+            params: vec![("response", response)],
+            ..self
+        })
+    }
+
+    /// Echoes the `state` the client sent with its request, if any.
+    pub fn state(self, state: Option<String>) -> Self {
+        match state {
+            Some(state) => self.param("state", state),
+            None => self,
+        }
+    }
+
+    /// The redirection URI with the response parameters appended to its query component,
+    /// preserving any query the client registered the URI with, or put in its fragment component.
+    fn location(&self) -> String {
+        let params = self.params.iter().map(|(k, v)| (*k, v.as_str()));
+        let mut location = self.redirect_uri.clone();
+        if self.mode.in_query() {
+            location.query_pairs_mut().extend_pairs(params);
+        } else {
+            let fragment = url::form_urlencoded::Serializer::new(String::new())
+                .extend_pairs(params)
+                .finish();
+            location.set_fragment(Some(&fragment));
+        }
+        location.to_string()
+    }
+
+    /// A page that has the end user's browser post the response parameters to the redirection
+    /// URI, as is.
+    fn form_post(&self) -> Template {
+        let params = self
+            .params
+            .iter()
+            .map(|(name, value)| context! { name, value })
+            .collect::<Vec<_>>();
+        Template::render(
+            "form_post",
+            context! { action: self.redirect_uri.as_str(), params },
+        )
+    }
+}
+
+impl<'r> Responder<'r, 'static> for AuthorizationResponse {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        match self.mode {
+            ResponseMode::FormPost | ResponseMode::FormPostJwt => {
+                let mut response = self.form_post().respond_to(request)?;
+                // The page carries codes and tokens.
+                response.set_raw_header("Cache-Control", "no-store");
+                Ok(response)
+            }
+            _ => Redirect::found(self.location()).respond_to(request),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Duration, Utc};
+use entity::{
+    authorization_codes::{self, Entity as AuthorizationCode},
+    clients::{self, Scope},
+};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+
+use crate::rest::clients::generate_secret;
+
+use super::{
+    hash_secret,
+    pkce::{Challenge, Method},
+    refresh_token,
+};
+
+/// What the end user authorized, recorded along with the code so that the token endpoint can act
+/// on it.
+pub struct Authorization {
+    pub redirect_uri: String,
+    pub scope: Scope,
+    pub nonce: Option<String>,
+    pub subject: String,
+
+    /// When the end user last actively authenticated.
+    pub auth_time: DateTime<Utc>,
+
+    /// The PKCE challenge the code verifier will be checked against.
+    pub code_challenge: Option<Challenge>,
+}
+
+/// Mints a new authorization code for `client` and persists its hash. The code itself is only
+/// ever handed to the client.
+pub async fn issue(
+    db: &DatabaseConnection,
+    client: &clients::Model,
+    authorization: Authorization,
+    lifetime: i64,
+) -> Result<String, DbErr> {
+    let code = generate_secret(32);
+    let grant = authorization_codes::ActiveModel {
+        code_hash: Set(hash_secret(&code)),
+        client_id: Set(client.id),
+        redirect_uri: Set(authorization.redirect_uri),
+        scope: Set(authorization.scope),
+        nonce: Set(authorization.nonce),
+        subject: Set(authorization.subject),
+        auth_time: Set(authorization.auth_time),
+        code_challenge: Set(authorization
+            .code_challenge
+            .as_ref()
+            .map(|challenge| challenge.challenge.clone())),
+        code_challenge_method: Set(authorization
+            .code_challenge
+            .map(|challenge| challenge.method.to_string())),
+        expires_at: Set(Utc::now() + Duration::seconds(lifetime)),
+        consumed: Set(false),
+        refresh_token_family: Set(Some(generate_secret(32))),
+        created_at: Set(Utc::now()),
+        ..Default::default()
+    };
+    grant.insert(db).await?;
+
+    Ok(code)
+}
+
+/// Marks the code as consumed and returns it, provided it exists, has not expired and has not
+/// been redeemed before. Codes are single-use: the conditional update guarantees that two
+/// concurrent requests cannot both redeem the same code. A code presented a second time was
+/// intercepted, so the refresh tokens issued for it are revoked (RFC 6749 section 4.1.2).
+pub async fn redeem(
+    db: &DatabaseConnection,
+    code: &str,
+) -> Result<Option<authorization_codes::Model>, DbErr> {
+    let Some(grant) = AuthorizationCode::find()
+        .filter(authorization_codes::Column::CodeHash.eq(hash_secret(code)))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    if grant.consumed {
+        replayed(db, &grant).await?;
+        return Ok(None);
+    }
+    if grant.expires_at <= Utc::now() {
+        return Ok(None);
+    }
+
+    let result = AuthorizationCode::update_many()
+        .col_expr(authorization_codes::Column::Consumed, Expr::value(true))
+        .filter(authorization_codes::Column::Id.eq(grant.id))
+        .filter(authorization_codes::Column::Consumed.eq(false))
+        .exec(db)
+        .await?;
+
+    if result.rows_affected == 1 {
+        return Ok(Some(grant));
+    }
+
+    replayed(db, &grant).await?;
+    Ok(None)
+}
+
+async fn replayed(
+    db: &DatabaseConnection,
+    grant: &authorization_codes::Model,
+) -> Result<(), DbErr> {
+    tracing::warn!("authorization code {} was replayed", grant.id);
+    if let Some(family) = &grant.refresh_token_family {
+        refresh_token::revoke_family(db, family).await?;
+    }
+    Ok(())
+}
+
+/// The PKCE challenge stored along with a code, if the authorization request carried one.
+pub fn challenge(grant: &authorization_codes::Model) -> Option<Challenge> {
+    let challenge = grant.code_challenge.clone()?;
+    let method = grant
+        .code_challenge_method
+        .as_deref()
+        .and_then(|method| method.parse().ok())
+        .unwrap_or(Method::Plain);
+    Some(Challenge { challenge, method })
+}
@@ -0,0 +1,165 @@
+use entity::{
+    clients::{self, Scope},
+    consents,
+};
+use rocket::{form::Form, http::Status, response::Redirect, State};
+use rocket_dyn_templates::{context, Template};
+use sea_orm::{
+    sea_query::OnConflict, ColumnTrait, DatabaseConnection, DbErr, EntityTrait, QueryFilter, Set,
+};
+
+use crate::App;
+
+use super::{
+    authorize::{self, AuthorizationResponse},
+    csrf::CsrfToken,
+    scope,
+    session::Session,
+    OidcError,
+};
+
+/// What `value` gives the client access to. Unknown scope values are shown as they are.
+fn describe(value: &str) -> &str {
+    scope::definition(value).map_or(value, |definition| definition.description)
+}
+
+#[derive(Debug, Clone, Copy, FromFormField)]
+pub enum Decision {
+    #[field(value = "allow")]
+    Allow,
+    #[field(value = "deny")]
+    Deny,
+}
+
+#[derive(FromForm)]
+pub struct ConsentPayload {
+    decision: Decision,
+
+    /// The authorization request to resume once the end user has decided, as a query string.
+    resume: String,
+
+    /// Proves that the consent screen itself was posted.
+    csrf_token: String,
+}
+
+/// The consent screen, asking the end user whether `client` may have `scope`.
+pub fn page(resume: &str, csrf: &CsrfToken, client: &clients::Model, scope: &Scope) -> Template {
+    let scopes = scope
+        .0
+        .iter()
+        .map(|value| context! { value, description: describe(value) })
+        .collect::<Vec<_>>();
+    Template::render(
+        "consent",
+        context! { resume, csrf_token: csrf.as_str(), client: &client.name, scopes },
+    )
+}
+
+/// Whether the end user already consented to the client having every scope value in `scope`.
+pub async fn covers(
+    db: &DatabaseConnection,
+    user_id: i32,
+    client_id: i32,
+    scope: &Scope,
+) -> Result<bool, DbErr> {
+    let consents = consents::Entity::find()
+        .filter(consents::Column::UserId.eq(user_id))
+        .filter(consents::Column::ClientId.eq(client_id))
+        .all(db)
+        .await?;
+    Ok(consents
+        .iter()
+        .any(|consent| scope.0.iter().all(|value| consent.scope.0.contains(value))))
+}
+
+/// Records that the end user consented to the client having `scope`. Scope sets are stored
+/// sorted, so that the same set requested in a different order maps to the same consent.
+/// `implicit` grants were not asked for, because the client is trusted.
+pub async fn grant(
+    db: &DatabaseConnection,
+    user_id: i32,
+    client_id: i32,
+    scope: &Scope,
+    implicit: bool,
+) -> Result<(), DbErr> {
+    let mut values = scope.0.clone();
+    values.sort();
+    values.dedup();
+
+    let consent = consents::ActiveModel {
+        user_id: Set(user_id),
+        client_id: Set(client_id),
+        scope: Set(Scope(values)),
+        implicit: Set(implicit),
+        created_at: Set(chrono::Utc::now()),
+        ..Default::default()
+    };
+    consents::Entity::insert(consent)
+        .on_conflict(
+            OnConflict::columns([
+                consents::Column::UserId,
+                consents::Column::ClientId,
+                consents::Column::Scope,
+            ])
+            .update_column(consents::Column::Implicit)
+            .to_owned(),
+        )
+        .exec_without_returning(db)
+        .await?;
+    Ok(())
+}
+
+#[derive(Responder)]
+pub enum ConsentOutcome {
+    Resume(Redirect),
+    Response(AuthorizationResponse),
+}
+
+#[post("/", data = "<payload>")]
+pub async fn consent(
+    app: &State<App>,
+    session: Option<Session>,
+    csrf: CsrfToken,
+    payload: Form<ConsentPayload>,
+) -> Result<ConsentOutcome, (Status, String)> {
+    if !csrf.verify(&payload.csrf_token) {
+        return Err((Status::Forbidden, "Invalid CSRF token".to_string()));
+    }
+    let resume = Redirect::to(format!("/authorize?{}", payload.resume));
+    // The end user signed out in the meantime; the authorization endpoint will ask them to sign
+    // in again.
+    let Some(session) = session else {
+        return Ok(ConsentOutcome::Resume(resume));
+    };
+    let (client, redirect_uri, request) = authorize::parse_resumed(app, &payload.resume).await?;
+
+    match payload.decision {
+        Decision::Allow => {
+            let scope = request
+                .scope(&client, app.config.scope_policy)
+                .map_err(|e| (Status::BadRequest, e.to_string()))?;
+            grant(
+                &app.seaorm_pool,
+                session.user_id(),
+                client.id,
+                &scope,
+                false,
+            )
+            .await
+            .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            Ok(ConsentOutcome::Resume(resume))
+        }
+        Decision::Deny => {
+            let response = AuthorizationResponse::error(redirect_uri, &OidcError::AccessDenied)
+                .mode(request.error_response_mode())
+                .state(request.state())
+                .sign(
+                    &app.keys.signing_key(),
+                    &app.config.issuer,
+                    &client.uuid.to_string(),
+                )
+                .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+            Ok(ConsentOutcome::Response(response))
+        }
+    }
+}
@@ -0,0 +1,51 @@
+use rocket::{
+    http::{Cookie, CookieJar, SameSite},
+    request::{self, FromRequest, Request},
+};
+use subtle::ConstantTimeEq;
+
+use crate::rest::clients::generate_secret;
+
+const COOKIE_NAME: &str = "csrf";
+
+/// The token that the provider's own forms carry, to tell them apart from forms another site has
+/// the browser post (cross-site request forgery). It is bound to a cookie the browser gets before
+/// signing in, which other sites can neither read nor set.
+#[derive(Debug)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// The token of the browser, issued on first use.
+    fn of(cookies: &CookieJar<'_>) -> Self {
+        if let Some(cookie) = cookies.get_private(COOKIE_NAME) {
+            return CsrfToken(cookie.value().to_string());
+        }
+        let token = generate_secret(32);
+        // Lax, so that the cookie survives a relying party redirecting the browser here, but is
+        // not sent along with a form another site posts.
+        let cookie = Cookie::build((COOKIE_NAME, token.clone()))
+            .http_only(true)
+            .same_site(SameSite::Lax);
+        cookies.add_private(cookie);
+        CsrfToken(token)
+    }
+
+    /// Whether a form posted by the browser carried its token. A browser that had no token yet
+    /// just got a new one, which no form can carry.
+    pub fn verify(&self, presented: &str) -> bool {
+        self.0.as_bytes().ct_eq(presented.as_bytes()).into()
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for CsrfToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        request::Outcome::Success(CsrfToken::of(request.cookies()))
+    }
+}
@@ -0,0 +1,76 @@
+use entity::clients::GrantType;
+use rocket::{serde::json::Json, State};
+use serde::Serialize;
+
+use crate::{config::Config, App};
+
+use super::{authorize, id_token, pkce, scope, signing, token};
+
+/// OpenID Provider Metadata, as described in OpenID Connect Discovery 1.0 section 3.
+#[derive(Debug, Serialize)]
+pub struct ProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    userinfo_endpoint: String,
+    response_types_supported: Vec<String>,
+    response_modes_supported: Vec<String>,
+    authorization_signing_alg_values_supported: Vec<String>,
+    grant_types_supported: Vec<String>,
+    scopes_supported: Vec<String>,
+    subject_types_supported: Vec<String>,
+    id_token_signing_alg_values_supported: Vec<String>,
+    token_endpoint_auth_methods_supported: Vec<String>,
+    claims_supported: Vec<String>,
+    code_challenge_methods_supported: Vec<String>,
+}
+
+fn strings<T: ToString>(values: &[T]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+impl ProviderMetadata {
+    pub fn new(config: &Config) -> Self {
+        let issuer = &config.issuer;
+        ProviderMetadata {
+            issuer: issuer.to_string(),
+            authorization_endpoint: format!("{}/authorize", issuer),
+            token_endpoint: format!("{}/token", issuer),
+            jwks_uri: format!("{}/.well-known/jwks.json", issuer),
+            userinfo_endpoint: format!("{}/userinfo", issuer),
+            response_types_supported: authorize::RESPONSE_TYPES
+                .iter()
+                .map(|types| strings(types).join(" "))
+                .collect(),
+            response_modes_supported: strings(authorize::RESPONSE_MODES),
+            // JWT secured authorization responses are signed only, encryption is not supported.
+            authorization_signing_alg_values_supported: vec![signing::ALGORITHM.to_string()],
+            // The implicit grant does not go through the token endpoint, but it is a grant all the
+            // same.
+            grant_types_supported: strings(
+                &[token::grant_types(config), vec![GrantType::Implicit]].concat(),
+            ),
+            scopes_supported: scope::SCOPES
+                .iter()
+                .map(|definition| definition.value.to_string())
+                .collect(),
+            // Subjects are the same for every client.
+            subject_types_supported: vec!["public".to_string()],
+            id_token_signing_alg_values_supported: vec![signing::ALGORITHM.to_string()],
+            token_endpoint_auth_methods_supported: strings(token::AUTH_METHODS),
+            claims_supported: id_token::CLAIMS
+                .iter()
+                .copied()
+                .chain(scope::claims().filter(|claim| !id_token::CLAIMS.contains(claim)))
+                .map(String::from)
+                .collect(),
+            code_challenge_methods_supported: strings(pkce::METHODS),
+        }
+    }
+}
+
+#[get("/openid-configuration")]
+pub fn openid_configuration(app: &State<App>) -> Json<ProviderMetadata> {
+    Json(ProviderMetadata::new(&app.config))
+}
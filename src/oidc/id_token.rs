@@ -0,0 +1,100 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+
+use super::signing::{self, SigningKey};
+
+/// Every claim an ID token may carry.
+pub const CLAIMS: &[&str] = &[
+    "iss",
+    "sub",
+    "aud",
+    "exp",
+    "iat",
+    "auth_time",
+    "nonce",
+    "at_hash",
+    "c_hash",
+];
+
+/// The claims of an ID token, as described in OIDC Core 2.
+#[derive(Debug, Serialize)]
+pub struct IdToken {
+    iss: String,
+    sub: String,
+    aud: String,
+    exp: i64,
+    iat: i64,
+    auth_time: i64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    nonce: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    at_hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    c_hash: Option<String>,
+    #[serde(flatten)]
+    claims: Map<String, Value>,
+}
+
+/// Hashes `value` with the hash algorithm of the signature (SHA-256 for RS256) and encodes the
+/// left-most half of the hash, as `at_hash` and `c_hash` are defined in OIDC Core 3.3.2.11.
+fn left_half_hash(value: &str) -> String {
+    let hash = Sha256::digest(value.as_bytes());
+    URL_SAFE_NO_PAD.encode(&hash[..hash.len() / 2])
+}
+
+impl IdToken {
+    pub fn new(
+        issuer: &str,
+        subject: &str,
+        audience: &str,
+        lifetime: i64,
+        auth_time: DateTime<Utc>,
+    ) -> Self {
+        let now = Utc::now().timestamp();
+        IdToken {
+            iss: issuer.to_string(),
+            sub: subject.to_string(),
+            aud: audience.to_string(),
+            exp: now + lifetime,
+            iat: now,
+            auth_time: auth_time.timestamp(),
+            nonce: None,
+            at_hash: None,
+            c_hash: None,
+            claims: Map::new(),
+        }
+    }
+
+    /// Passes the `nonce` of the authentication request through to the token.
+    pub fn nonce(mut self, nonce: Option<String>) -> Self {
+        self.nonce = nonce;
+        self
+    }
+
+    /// Binds the token to the access token issued alongside it.
+    pub fn access_token(mut self, access_token: &str) -> Self {
+        self.at_hash = Some(left_half_hash(access_token));
+        self
+    }
+
+    /// Binds the token to the authorization code it was issued for.
+    pub fn code(mut self, code: &str) -> Self {
+        self.c_hash = Some(left_half_hash(code));
+        self
+    }
+
+    /// Carries claims about the end user, for when no access token is issued that the client
+    /// could take to the UserInfo endpoint (OIDC Core 5.4).
+    pub fn claims(mut self, mut claims: Map<String, Value>) -> Self {
+        claims.remove("sub");
+        self.claims = claims;
+        self
+    }
+
+    pub fn sign(&self, key: &SigningKey) -> Result<String, signing::Error> {
+        key.sign(self)
+    }
+}
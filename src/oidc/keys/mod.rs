@@ -0,0 +1,194 @@
+use std::{
+    sync::{Arc, RwLock},
+    time::Duration as StdDuration,
+};
+
+use chrono::{DateTime, Duration, Utc};
+use entity::keys;
+use jsonwebtoken::Validation;
+use rocket::{serde::json::Json, State};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, QueryFilter,
+    QueryOrder, Set,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::{config::Config, rest::clients::generate_secret, App};
+
+use super::signing::{self, Jwk, SigningKey, ALGORITHM};
+
+/// How often the rotation task checks whether the keys need attention.
+const ROTATION_CHECK_INTERVAL: StdDuration = StdDuration::from_secs(60);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("Database error: {0}")]
+    Db(#[from] sea_orm::DbErr),
+
+    #[error("Signing error: {0}")]
+    Signing(#[from] signing::Error),
+}
+
+struct Keys {
+    /// The key new tokens are signed with.
+    signing: Arc<SigningKey>,
+
+    /// Every key relying parties may need to verify a token: keys that are about to be used,
+    /// the signing key and retired keys whose tokens may not have expired yet.
+    published: Vec<Arc<SigningKey>>,
+}
+
+/// The provider's signing keys. A key goes through the following lifecycle:
+///
+/// 1. it is created and published in the JWKS, but not used yet;
+/// 2. once `activates_at` has passed, it becomes the signing key and the key it replaces is
+///    retired;
+/// 3. once every token signed by a retired key has expired, the key is deleted.
+#[derive(Clone)]
+pub struct Keyring(Arc<RwLock<Keys>>);
+
+async fn create_key(
+    db: &DatabaseConnection,
+    activates_at: DateTime<Utc>,
+) -> Result<keys::Model, Error> {
+    // Generating an RSA key takes long enough to stall the other requests of a runtime worker.
+    let private_key = rocket::tokio::task::spawn_blocking(signing::generate_private_key)
+        .await
+        .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()))?;
+    let key = keys::ActiveModel {
+        kid: Set(generate_secret(16)),
+        algorithm: Set(ALGORITHM.to_string()),
+        private_key: Set(private_key),
+        created_at: Set(Utc::now()),
+        activates_at: Set(activates_at),
+        ..Default::default()
+    };
+    Ok(key.insert(db).await?)
+}
+
+/// Brings the keys in the database in line with the rotation policy, and returns the ones that
+/// should be loaded.
+async fn rotate(db: &DatabaseConnection, config: &Config) -> Result<Keys, Error> {
+    let now = Utc::now();
+
+    // Drop retired keys once the tokens they signed have all expired.
+    keys::Entity::delete_many()
+        .filter(keys::Column::RetiredAt.lte(now - Duration::seconds(config.token_lifetime)))
+        .exec(db)
+        .await?;
+
+    let newest = keys::Entity::find()
+        .order_by_desc(keys::Column::CreatedAt)
+        .one(db)
+        .await?;
+    match newest {
+        // The very first key is used right away, there is nothing to sign tokens with otherwise.
+        None => {
+            tracing::info!("no signing key found, generating one");
+            create_key(db, now).await?;
+        }
+        Some(key) if key.created_at + Duration::days(config.key_rotation_interval) <= now => {
+            tracing::info!("signing key {} is due for rotation", key.kid);
+            create_key(db, now + Duration::seconds(config.key_publication_delay)).await?;
+        }
+        Some(_) => {}
+    }
+
+    let signing = keys::Entity::find()
+        .filter(keys::Column::RetiredAt.is_null())
+        .filter(keys::Column::ActivatesAt.lte(now))
+        .order_by_desc(keys::Column::ActivatesAt)
+        .one(db)
+        .await?
+        .expect("a signing key has been activated");
+
+    // Every key that was in use before the current signing key is superseded by it.
+    keys::Entity::update_many()
+        .col_expr(keys::Column::RetiredAt, Expr::value(now))
+        .filter(keys::Column::RetiredAt.is_null())
+        .filter(keys::Column::ActivatesAt.lt(signing.activates_at))
+        .exec(db)
+        .await?;
+
+    let published = keys::Entity::find()
+        .order_by_desc(keys::Column::CreatedAt)
+        .all(db)
+        .await?
+        .into_iter()
+        .map(|key| Ok(Arc::new(SigningKey::from_pem(key.kid, &key.private_key)?)))
+        .collect::<Result<Vec<_>, Error>>()?;
+
+    Ok(Keys {
+        signing: Arc::new(SigningKey::from_pem(signing.kid, &signing.private_key)?),
+        published,
+    })
+}
+
+impl Keyring {
+    /// Loads the signing keys from the database, generating the first one if there are none.
+    pub async fn load(db: &DatabaseConnection, config: &Config) -> Result<Self, Error> {
+        Ok(Keyring(Arc::new(RwLock::new(rotate(db, config).await?))))
+    }
+
+    /// Rotates the keys according to the configured policy for as long as the server runs.
+    pub async fn rotate_periodically(self, db: DatabaseConnection, config: Config) {
+        // The keys were just rotated when the keyring was loaded.
+        let start = rocket::tokio::time::Instant::now() + ROTATION_CHECK_INTERVAL;
+        let mut interval = rocket::tokio::time::interval_at(start, ROTATION_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match rotate(&db, &config).await {
+                Ok(keys) => *self.0.write().expect("keyring poisoned") = keys,
+                Err(e) => tracing::error!("signing key rotation failed: {}", e),
+            }
+        }
+    }
+
+    /// The key new tokens are signed with.
+    pub fn signing_key(&self) -> Arc<SigningKey> {
+        self.0.read().expect("keyring poisoned").signing.clone()
+    }
+
+    /// Checks a token the provider signed with any of its published keys, and returns its claims.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<T, signing::Error> {
+        let kid = jsonwebtoken::decode_header(token)?.kid;
+        let key = self
+            .0
+            .read()
+            .expect("keyring poisoned")
+            .published
+            .iter()
+            .find(|key| Some(key.kid()) == kid.as_deref())
+            .cloned()
+            .ok_or(signing::Error::UnknownKey(kid))?;
+        key.verify(token, validation)
+    }
+
+    pub fn jwks(&self) -> Jwks {
+        Jwks {
+            keys: self
+                .0
+                .read()
+                .expect("keyring poisoned")
+                .published
+                .iter()
+                .map(|key| key.jwk())
+                .collect(),
+        }
+    }
+}
+
+/// A JSON Web Key Set (RFC 7517 5).
+#[derive(Debug, Serialize)]
+pub struct Jwks {
+    keys: Vec<Jwk>,
+}
+
+#[get("/jwks.json")]
+pub fn jwks(app: &State<App>) -> Json<Jwks> {
+    Json(app.keys.jwks())
+}
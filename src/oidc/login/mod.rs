@@ -0,0 +1,170 @@
+use entity::users;
+use rocket::{form::Form, http::CookieJar, http::Status, response::Redirect, State};
+use rocket_dyn_templates::{context, Template};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+
+use crate::{password, App};
+
+use super::{csrf::CsrfToken, session::Session};
+
+#[derive(FromForm)]
+pub struct LoginPayload {
+    username: String,
+    password: String,
+
+    /// The authorization request to resume once the end user is signed in, as a query string.
+    resume: String,
+
+    /// Proves that the login form itself was posted, and not a form on another site signing the
+    /// end user in to an account of its choosing.
+    csrf_token: String,
+}
+
+/// The login form. `resume` is the query string of the authorization request that sent the end
+/// user here.
+pub fn page(
+    resume: &str,
+    csrf: &CsrfToken,
+    username: Option<&str>,
+    error: Option<&str>,
+) -> Template {
+    Template::render(
+        "login",
+        context! { resume, csrf_token: csrf.as_str(), username, error },
+    )
+}
+
+/// The outcome of checking a username and password.
+pub(crate) enum Authentication {
+    Success(Box<users::Model>),
+    Failure,
+
+    /// Too many attempts failed for the username lately, the password was not even checked.
+    Throttled,
+}
+
+/// The account chooser, for `prompt=select_account`: the end user either goes on as `user`, or
+/// signs in with another account. Both are links resuming the authorization request.
+pub fn select_account_page(keep: &str, switch: &str, user: &users::Model) -> Template {
+    Template::render(
+        "select_account",
+        context! {
+            keep,
+            switch,
+            username: &user.username,
+            name: user.name.as_deref().unwrap_or(&user.username),
+        },
+    )
+}
+
+/// Finds the user with `username`, provided `password` is theirs. Every way of signing in with a
+/// password goes through here, so that they all share the same throttle.
+pub(crate) async fn authenticate(
+    app: &App,
+    username: &str,
+    password: &str,
+) -> Result<Authentication, sea_orm::DbErr> {
+    if !app.password_throttle.allows(username) {
+        tracing::warn!("authentication of {} throttled", username);
+        return Ok(Authentication::Throttled);
+    }
+    let user = users::Entity::find()
+        .filter(users::Column::Username.eq(username))
+        .one(&app.seaorm_pool)
+        .await?;
+    // Argon2 is slow on purpose, too slow to run on a runtime worker.
+    let password = password.to_string();
+    let password_hash = user.as_ref().map(|user| user.password_hash.clone());
+    let verified = rocket::tokio::task::spawn_blocking(move || match password_hash {
+        Some(password_hash) => password::verify(&password, &password_hash),
+        None => {
+            password::verify_nothing(&password);
+            false
+        }
+    })
+    .await
+    .unwrap_or_else(|e| std::panic::resume_unwind(e.into_panic()));
+    match user.filter(|_| verified) {
+        Some(user) => {
+            app.password_throttle.success(username);
+            Ok(Authentication::Success(Box::new(user)))
+        }
+        None => {
+            app.password_throttle.failure(username);
+            Ok(Authentication::Failure)
+        }
+    }
+}
+
+/// Checks the credentials and, if they are valid, replaces the browser's session with a new one.
+async fn sign_in(
+    app: &App,
+    cookies: &CookieJar<'_>,
+    previous: Option<Session>,
+    payload: &LoginPayload,
+) -> Result<Authentication, sea_orm::DbErr> {
+    let authentication = authenticate(app, &payload.username, &payload.password).await?;
+    if let Authentication::Success(user) = &authentication {
+        if let Some(previous) = previous {
+            previous.end(&app.seaorm_pool).await?;
+        }
+        Session::start(&app.seaorm_pool, cookies, user.id, &["pwd"]).await?;
+    }
+    Ok(authentication)
+}
+
+#[post("/", data = "<payload>")]
+pub async fn login(
+    app: &State<App>,
+    cookies: &CookieJar<'_>,
+    previous: Option<Session>,
+    csrf: CsrfToken,
+    payload: Form<LoginPayload>,
+) -> Result<Redirect, (Status, Template)> {
+    if !csrf.verify(&payload.csrf_token) {
+        return Err((
+            Status::Forbidden,
+            page(
+                &payload.resume,
+                &csrf,
+                Some(&payload.username),
+                Some("The form expired, please sign in again"),
+            ),
+        ));
+    }
+    match sign_in(app, cookies, previous, &payload).await {
+        Ok(Authentication::Success(_)) => {
+            Ok(Redirect::to(format!("/authorize?{}", payload.resume)))
+        }
+        Ok(Authentication::Throttled) => Err((
+            Status::TooManyRequests,
+            page(
+                &payload.resume,
+                &csrf,
+                Some(&payload.username),
+                Some("Too many failed attempts, please try again later"),
+            ),
+        )),
+        Ok(Authentication::Failure) => Err((
+            Status::Unauthorized,
+            page(
+                &payload.resume,
+                &csrf,
+                Some(&payload.username),
+                Some("Invalid username or password"),
+            ),
+        )),
+        Err(e) => {
+            tracing::error!("login failed: {}", e);
+            Err((
+                Status::InternalServerError,
+                page(
+                    &payload.resume,
+                    &csrf,
+                    Some(&payload.username),
+                    Some("Something went wrong, please try again"),
+                ),
+            ))
+        }
+    }
+}
@@ -1,32 +1,113 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+
+pub mod access_token;
 pub mod authorize;
+pub mod code;
+pub mod consent;
+pub mod csrf;
+pub mod discovery;
+pub mod id_token;
+pub mod keys;
+pub mod login;
+pub mod pkce;
+pub mod refresh_token;
+pub mod scope;
+pub mod session;
+pub mod signing;
+pub mod throttle;
+pub mod token;
+pub mod userinfo;
 
-#[allow(dead_code)]
 #[derive(Debug, thiserror::Error)]
 pub enum OidcError {
     #[error("Access denied")]
     AccessDenied,
 
+    #[error("Account selection required")]
+    AccountSelectionRequired,
+
+    #[error("Consent required")]
+    ConsentRequired,
+
+    #[error("Interaction required")]
+    InteractionRequired,
+
     #[error("Invalid client")]
     InvalidClient,
 
     #[error("Invalid grant")]
-    InvalidRequest,
+    InvalidGrant,
+
+    #[error("Invalid request: {0}")]
+    InvalidRequest(&'static str),
 
     #[error("Invalid scope")]
     InvalidScope,
 
     #[error("Invalid token")]
-    ServerError,
+    InvalidToken,
+
+    #[error("Insufficient scope")]
+    InsufficientScope,
 
-    #[error("Temporarily unavailable")]
-    TemporarilyUnavailable,
+    #[error("Login required")]
+    LoginRequired,
+
+    #[error("Server error")]
+    ServerError,
 
     #[error("Unauthorized client")]
     UnauthorizedClient,
 
     #[error("Unsupported grant type")]
+    UnsupportedGrantType,
+
+    #[error("Unsupported response type")]
     UnsupportedResponseType,
+}
+
+impl OidcError {
+    /// The error code defined by RFC 6749 (or OIDC Core 3.1.2.6, or RFC 6750 3.1) for this error.
+    pub fn code(&self) -> &'static str {
+        match self {
+            OidcError::AccessDenied => "access_denied",
+            OidcError::AccountSelectionRequired => "account_selection_required",
+            OidcError::ConsentRequired => "consent_required",
+            OidcError::InteractionRequired => "interaction_required",
+            OidcError::InvalidClient => "invalid_client",
+            OidcError::InvalidGrant => "invalid_grant",
+            OidcError::InvalidRequest(_) => "invalid_request",
+            OidcError::InvalidScope => "invalid_scope",
+            OidcError::InvalidToken => "invalid_token",
+            OidcError::InsufficientScope => "insufficient_scope",
+            OidcError::LoginRequired => "login_required",
+            OidcError::ServerError => "server_error",
+            OidcError::UnauthorizedClient => "unauthorized_client",
+            OidcError::UnsupportedGrantType => "unsupported_grant_type",
+            OidcError::UnsupportedResponseType => "unsupported_response_type",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct ErrorResponse {
+    error: &'static str,
+    error_description: String,
+}
+
+impl From<&OidcError> for ErrorResponse {
+    fn from(err: &OidcError) -> Self {
+        ErrorResponse {
+            error: err.code(),
+            error_description: err.to_string(),
+        }
+    }
+}
 
-    #[error("Unspecified error: {0}")]
-    Unspecified(String),
+/// Authorization codes and refresh tokens are only stored hashed, so that a leaked table does not
+/// hand out working credentials. They are random enough for a plain SHA-256 to do.
+pub(crate) fn hash_secret(secret: &str) -> String {
+    URL_SAFE_NO_PAD.encode(Sha256::digest(secret.as_bytes()))
 }
@@ -0,0 +1,143 @@
+use std::str::FromStr;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+
+use super::OidcError;
+
+/// How a code verifier is transformed into its challenge (RFC 7636 4.2).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    S256,
+    Plain,
+}
+
+/// The transformations the provider supports.
+pub const METHODS: &[Method] = &[Method::S256, Method::Plain];
+
+impl Method {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Method::S256 => "S256",
+            Method::Plain => "plain",
+        }
+    }
+}
+
+impl std::fmt::Display for Method {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl FromStr for Method {
+    type Err = OidcError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "S256" => Ok(Method::S256),
+            "plain" => Ok(Method::Plain),
+            _ => Err(OidcError::InvalidRequest(
+                "unsupported code_challenge_method",
+            )),
+        }
+    }
+}
+
+/// Whether `value` is a well-formed code verifier, or challenge: 43 to 128 characters from the
+/// unreserved set of RFC 3986.
+fn is_well_formed(value: &str) -> bool {
+    (43..=128).contains(&value.len())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '.' | '_' | '~'))
+}
+
+/// A code challenge sent with an authorization request.
+#[derive(Debug, Clone)]
+pub struct Challenge {
+    pub challenge: String,
+    pub method: Method,
+}
+
+impl Challenge {
+    /// Reads the challenge of an authorization request, if it carries one. The method defaults
+    /// to `plain` when omitted (RFC 7636 4.3).
+    pub fn parse(challenge: Option<&str>, method: Option<&str>) -> Result<Option<Self>, OidcError> {
+        let Some(challenge) = challenge else {
+            if method.is_some() {
+                return Err(OidcError::InvalidRequest("missing code_challenge"));
+            }
+            return Ok(None);
+        };
+        if !is_well_formed(challenge) {
+            return Err(OidcError::InvalidRequest("malformed code_challenge"));
+        }
+        let method = method.map_or(Ok(Method::Plain), Method::from_str)?;
+        Ok(Some(Challenge {
+            challenge: challenge.to_string(),
+            method,
+        }))
+    }
+
+    /// Whether `verifier` is the secret this challenge was derived from (RFC 7636 4.6).
+    pub fn verify(&self, verifier: &str) -> bool {
+        if !is_well_formed(verifier) {
+            return false;
+        }
+        let derived = match self.method {
+            Method::S256 => URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes())),
+            Method::Plain => verifier.to_string(),
+        };
+        derived == self.challenge
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example of RFC 7636 Appendix B.
+    const VERIFIER: &str = "dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk";
+    const CHALLENGE: &str = "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM";
+
+    #[test]
+    fn s256_matches_rfc_example() {
+        let challenge = Challenge::parse(Some(CHALLENGE), Some("S256"))
+            .unwrap()
+            .unwrap();
+        assert!(challenge.verify(VERIFIER));
+        assert!(!challenge.verify(CHALLENGE));
+    }
+
+    #[test]
+    fn plain_is_the_default() {
+        let challenge = Challenge::parse(Some(VERIFIER), None).unwrap().unwrap();
+        assert_eq!(challenge.method, Method::Plain);
+        assert!(challenge.verify(VERIFIER));
+        assert!(!challenge.verify(&VERIFIER.replace('d', "e")));
+    }
+
+    #[test]
+    fn malformed_verifiers_are_refused() {
+        assert!(Challenge::parse(Some(&VERIFIER[..42]), None).is_err());
+        assert!(!is_well_formed(&format!("{}+", &VERIFIER[..42])));
+        assert!(!is_well_formed(&format!("{} ", &VERIFIER[..42])));
+        assert!(is_well_formed(&"~._-".repeat(32)));
+
+        // The verifier is checked on its own, even if it hashes to the challenge.
+        let long = "a".repeat(129);
+        let derived = URL_SAFE_NO_PAD.encode(Sha256::digest(long.as_bytes()));
+        let challenge = Challenge::parse(Some(&derived), Some("S256"))
+            .unwrap()
+            .unwrap();
+        assert!(!challenge.verify(&long));
+    }
+
+    #[test]
+    fn method_without_challenge_is_refused() {
+        assert!(Challenge::parse(None, Some("S256")).is_err());
+        assert!(Challenge::parse(None, None).unwrap().is_none());
+        assert!(Challenge::parse(Some(CHALLENGE), Some("S512")).is_err());
+    }
+}
@@ -0,0 +1,113 @@
+use chrono::{DateTime, Duration, Utc};
+use entity::{
+    clients::{self, Scope},
+    refresh_tokens,
+};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, DatabaseConnection, DbErr, EntityTrait,
+    QueryFilter, Set,
+};
+
+use crate::rest::clients::generate_secret;
+
+use super::hash_secret;
+
+/// What the end user granted the client offline access to.
+pub struct Grant {
+    pub subject: String,
+    pub scope: Scope,
+
+    /// When the end user last actively authenticated.
+    pub auth_time: DateTime<Utc>,
+}
+
+/// Mints a new refresh token for `client` and persists it. A token issued for an authorization
+/// code starts the family reserved on the code, one issued for a password starts a new family;
+/// rotated tokens are issued in the family of the token they replace.
+pub async fn issue(
+    db: &DatabaseConnection,
+    client: &clients::Model,
+    grant: Grant,
+    family: Option<String>,
+    lifetime: i64,
+) -> Result<String, DbErr> {
+    let token = generate_secret(48);
+    let now = Utc::now();
+    let refresh_token = refresh_tokens::ActiveModel {
+        token_hash: Set(hash_secret(&token)),
+        family: Set(family.unwrap_or_else(|| generate_secret(32))),
+        client_id: Set(client.id),
+        subject: Set(grant.subject),
+        scope: Set(grant.scope),
+        auth_time: Set(grant.auth_time),
+        expires_at: Set(now + Duration::seconds(lifetime)),
+        consumed: Set(false),
+        revoked: Set(false),
+        created_at: Set(now),
+        ..Default::default()
+    };
+    refresh_token.insert(db).await?;
+
+    Ok(token)
+}
+
+/// Looks up a refresh token, provided it exists, has not expired and has not been revoked or used
+/// before. Refresh tokens are rotated on every use, so presenting one a second time means it
+/// leaked: every token of its family is revoked then, including the one the legitimate client
+/// currently holds (OAuth 2.0 Security BCP 4.14.2).
+pub async fn find(
+    db: &DatabaseConnection,
+    token: &str,
+) -> Result<Option<refresh_tokens::Model>, DbErr> {
+    let Some(refresh_token) = refresh_tokens::Entity::find()
+        .filter(refresh_tokens::Column::TokenHash.eq(hash_secret(token)))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    if refresh_token.revoked || refresh_token.expires_at <= Utc::now() {
+        return Ok(None);
+    }
+    if refresh_token.consumed {
+        tracing::warn!("refresh token {} was reused", refresh_token.id);
+        revoke_family(db, &refresh_token.family).await?;
+        return Ok(None);
+    }
+    Ok(Some(refresh_token))
+}
+
+/// Marks a token returned by [`find`] as used, once the request it came with has been validated.
+/// Returns whether this request is the one that used it; if another request got there first, the
+/// token was reused and its family is revoked.
+pub async fn consume(
+    db: &DatabaseConnection,
+    refresh_token: &refresh_tokens::Model,
+) -> Result<bool, DbErr> {
+    let result = refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::Consumed, Expr::value(true))
+        .filter(refresh_tokens::Column::Id.eq(refresh_token.id))
+        .filter(refresh_tokens::Column::Consumed.eq(false))
+        .exec(db)
+        .await?;
+    if result.rows_affected == 1 {
+        return Ok(true);
+    }
+
+    tracing::warn!("refresh token {} was reused", refresh_token.id);
+    revoke_family(db, &refresh_token.family).await?;
+    Ok(false)
+}
+
+/// Revokes every refresh token of `family`, once one of them or the authorization code they
+/// descend from was used twice.
+pub async fn revoke_family(db: &DatabaseConnection, family: &str) -> Result<(), DbErr> {
+    tracing::warn!("revoking refresh token family {}", family);
+    refresh_tokens::Entity::update_many()
+        .col_expr(refresh_tokens::Column::Revoked, Expr::value(true))
+        .filter(refresh_tokens::Column::Family.eq(family))
+        .exec(db)
+        .await?;
+    Ok(())
+}
@@ -0,0 +1,182 @@
+use std::str::FromStr;
+
+use entity::clients::Scope;
+
+use super::OidcError;
+
+/// A scope value the provider understands.
+#[derive(Debug)]
+pub struct ScopeDefinition {
+    pub value: &'static str,
+
+    /// What the scope gives the client access to, in terms the end user understands.
+    pub description: &'static str,
+
+    /// The claims about the end user the scope releases (OIDC Core 5.4). Claims are named after
+    /// the columns of the `users` table, see [`userinfo::release`](super::userinfo::release).
+    pub claims: &'static [&'static str],
+}
+
+/// Every scope value the provider understands. Custom scopes are added here, and consent,
+/// discovery and the UserInfo endpoint all pick them up.
+pub const SCOPES: &[ScopeDefinition] = &[
+    ScopeDefinition {
+        value: "openid",
+        description: "Know who you are",
+        claims: &["sub"],
+    },
+    ScopeDefinition {
+        value: "profile",
+        description: "See your name and basic profile information",
+        claims: &[
+            "name",
+            "family_name",
+            "given_name",
+            "middle_name",
+            "nickname",
+            "preferred_username",
+            "profile",
+            "picture",
+            "website",
+            "gender",
+            "birthdate",
+            "zoneinfo",
+            "locale",
+            "updated_at",
+        ],
+    },
+    ScopeDefinition {
+        value: "email",
+        description: "See your email address",
+        claims: &["email", "email_verified"],
+    },
+    ScopeDefinition {
+        value: "address",
+        description: "See your postal address",
+        claims: &["address"],
+    },
+    ScopeDefinition {
+        value: "phone",
+        description: "See your phone number",
+        claims: &["phone_number", "phone_number_verified"],
+    },
+    ScopeDefinition {
+        value: "offline_access",
+        description: "Keep access to your information when you are not signed in",
+        claims: &[],
+    },
+];
+
+pub fn definition(value: &str) -> Option<&'static ScopeDefinition> {
+    SCOPES.iter().find(|definition| definition.value == value)
+}
+
+/// Every claim some scope releases.
+pub fn claims() -> impl Iterator<Item = &'static str> {
+    SCOPES
+        .iter()
+        .flat_map(|definition| definition.claims.iter().copied())
+}
+
+/// What to do with requested scope values the client has not registered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ScopePolicy {
+    /// Leave them out of the grant; the client learns what it was granted from the token
+    /// response (RFC 6749 3.3).
+    Drop,
+
+    /// Refuse the request with `invalid_scope`.
+    Reject,
+}
+
+impl FromStr for ScopePolicy {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(ScopePolicy::Drop),
+            "reject" => Ok(ScopePolicy::Reject),
+            _ => Err(()),
+        }
+    }
+}
+
+/// Whether `c` may appear in a scope token: `%x21 / %x23-5B / %x5D-7E`, i.e. printable ASCII
+/// except space, `"` and `\`.
+fn is_scope_char(c: char) -> bool {
+    matches!(c, '\x21' | '\x23'..='\x5B' | '\x5D'..='\x7E')
+}
+
+/// Parses a `scope` parameter: a list of space-delimited, case-sensitive strings, in which
+/// repeated values are ignored (RFC 6749 3.3).
+pub fn parse(value: &str) -> Result<Scope, OidcError> {
+    let mut scope = Vec::new();
+    for token in value.split(' ').filter(|token| !token.is_empty()) {
+        if !token.chars().all(is_scope_char) {
+            return Err(OidcError::InvalidScope);
+        }
+        if !scope.iter().any(|value| value == token) {
+            scope.push(token.to_string());
+        }
+    }
+    Ok(Scope(scope))
+}
+
+/// Narrows `requested` down to what the client registered, so that a client cannot obtain more
+/// by simply asking for it.
+pub fn restrict(
+    requested: Scope,
+    registered: &Scope,
+    policy: ScopePolicy,
+) -> Result<Scope, OidcError> {
+    let (granted, unregistered): (Vec<_>, Vec<_>) = requested
+        .0
+        .into_iter()
+        .partition(|value| registered.0.contains(value));
+    if policy == ScopePolicy::Reject && !unregistered.is_empty() {
+        return Err(OidcError::InvalidScope);
+    }
+    Ok(Scope(granted))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scope(values: &[&str]) -> Scope {
+        Scope(values.iter().map(|value| value.to_string()).collect())
+    }
+
+    #[test]
+    fn parse_splits_on_spaces_and_ignores_repeats() {
+        let parsed = parse("openid  profile openid email").unwrap();
+        assert_eq!(parsed, scope(&["openid", "profile", "email"]));
+    }
+
+    #[test]
+    fn parse_refuses_forbidden_characters() {
+        assert!(parse("openid \"profile\"").is_err());
+        assert!(parse("openid pro\\file").is_err());
+        assert!(parse("openid\tprofile").is_err());
+        assert!(parse("openid,profile").is_ok());
+    }
+
+    #[test]
+    fn restrict_drops_unregistered_values() {
+        let registered = scope(&["openid", "email"]);
+        let requested = scope(&["openid", "profile", "email"]);
+        let granted = restrict(requested, &registered, ScopePolicy::Drop).unwrap();
+        assert_eq!(granted, scope(&["openid", "email"]));
+    }
+
+    #[test]
+    fn restrict_rejects_unregistered_values() {
+        let registered = scope(&["openid", "email"]);
+        let requested = scope(&["openid", "profile"]);
+        assert!(restrict(requested, &registered, ScopePolicy::Reject).is_err());
+
+        let requested = scope(&["email"]);
+        let granted = restrict(requested, &registered, ScopePolicy::Reject).unwrap();
+        assert_eq!(granted, scope(&["email"]));
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::Duration as StdDuration;
+
+use chrono::{DateTime, Duration, Utc};
+use entity::sessions::{self, AuthenticationMethods};
+use rocket::{
+    http::{Cookie, CookieJar, SameSite, Status},
+    request::{self, FromRequest, Request},
+    State,
+};
+use sea_orm::{
+    sea_query::Expr, ActiveModelTrait, ColumnTrait, Condition, DatabaseConnection, DbErr,
+    EntityTrait, QueryFilter, Set,
+};
+
+use crate::{config::Config, rest::clients::generate_secret, App};
+
+const COOKIE_NAME: &str = "session";
+
+/// How often expired sessions are deleted.
+const PURGE_INTERVAL: StdDuration = StdDuration::from_secs(3600);
+
+/// The end user signed in to the provider in this browser. The browser only holds the session
+/// identifier, in an encrypted cookie; the session itself lives in the `sessions` table.
+#[derive(Debug)]
+pub struct Session(sessions::Model);
+
+impl Session {
+    /// Records that `user_id` just authenticated using the given methods (as `amr` values, see
+    /// RFC 8176), and points the browser to the new session.
+    pub async fn start(
+        db: &DatabaseConnection,
+        cookies: &CookieJar<'_>,
+        user_id: i32,
+        amr: &[&str],
+    ) -> Result<Self, DbErr> {
+        let now = Utc::now();
+        let session = sessions::ActiveModel {
+            session_id: Set(generate_secret(32)),
+            user_id: Set(user_id),
+            auth_time: Set(now),
+            amr: Set(AuthenticationMethods(
+                amr.iter().map(|method| method.to_string()).collect(),
+            )),
+            last_active_at: Set(now),
+            created_at: Set(now),
+            ..Default::default()
+        };
+        let session = session.insert(db).await?;
+
+        // Lax rather than Strict, so that the cookie is sent along when a relying party
+        // redirects the browser to the authorization endpoint.
+        let cookie = Cookie::build((COOKIE_NAME, session.session_id.clone()))
+            .http_only(true)
+            .same_site(SameSite::Lax);
+        cookies.add_private(cookie);
+
+        Ok(Session(session))
+    }
+
+    /// Ends the session, e.g. because the end user signed in again.
+    pub async fn end(self, db: &DatabaseConnection) -> Result<(), DbErr> {
+        sessions::Entity::delete_by_id(self.0.id).exec(db).await?;
+        Ok(())
+    }
+
+    pub fn user_id(&self) -> i32 {
+        self.0.user_id
+    }
+
+    pub fn auth_time(&self) -> DateTime<Utc> {
+        self.0.auth_time
+    }
+}
+
+/// Sessions that ended by themselves: the end user has not come back for longer than the idle
+/// timeout, or signed in longer than the session lifetime ago.
+fn expired(config: &Config) -> Condition {
+    let now = Utc::now();
+    Condition::any()
+        .add(
+            sessions::Column::LastActiveAt
+                .lte(now - Duration::seconds(config.session_idle_timeout)),
+        )
+        .add(sessions::Column::CreatedAt.lte(now - Duration::seconds(config.session_lifetime)))
+}
+
+/// Deletes expired sessions for as long as the server runs. They are refused anyway, this only
+/// keeps the table from growing.
+pub async fn purge_periodically(db: DatabaseConnection, config: Config) {
+    let mut interval = rocket::tokio::time::interval(PURGE_INTERVAL);
+    loop {
+        interval.tick().await;
+        let result = sessions::Entity::delete_many()
+            .filter(expired(&config))
+            .exec(&db)
+            .await;
+        match result {
+            Ok(result) => tracing::debug!("purged {} expired sessions", result.rows_affected),
+            Err(e) => tracing::error!("purging expired sessions failed: {}", e),
+        }
+    }
+}
+
+async fn find_session(
+    db: &DatabaseConnection,
+    config: &Config,
+    session_id: &str,
+) -> Result<Option<Session>, DbErr> {
+    let Some(session) = sessions::Entity::find()
+        .filter(sessions::Column::SessionId.eq(session_id))
+        .one(db)
+        .await?
+    else {
+        return Ok(None);
+    };
+
+    let now = Utc::now();
+    if session.last_active_at + Duration::seconds(config.session_idle_timeout) <= now
+        || session.created_at + Duration::seconds(config.session_lifetime) <= now
+    {
+        sessions::Entity::delete_by_id(session.id).exec(db).await?;
+        return Ok(None);
+    }
+
+    sessions::Entity::update_many()
+        .col_expr(sessions::Column::LastActiveAt, Expr::value(Utc::now()))
+        .filter(sessions::Column::Id.eq(session.id))
+        .exec(db)
+        .await?;
+
+    Ok(Some(Session(session)))
+}
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Session {
+    type Error = DbErr;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let Some(cookie) = request.cookies().get_private(COOKIE_NAME) else {
+            return request::Outcome::Forward(Status::Unauthorized);
+        };
+        let app = request
+            .guard::<&State<App>>()
+            .await
+            .expect("app state is managed");
+
+        match find_session(&app.seaorm_pool, &app.config, cookie.value()).await {
+            Ok(Some(session)) => request::Outcome::Success(session),
+            Ok(None) => {
+                request.cookies().remove_private(COOKIE_NAME);
+                request::Outcome::Forward(Status::Unauthorized)
+            }
+            Err(e) => request::Outcome::Error((Status::InternalServerError, e)),
+        }
+    }
+}
@@ -0,0 +1,114 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use jsonwebtoken::{Algorithm, DecodingKey, EncodingKey, Header, Validation};
+use rsa::{
+    pkcs1::EncodeRsaPrivateKey,
+    pkcs8::{DecodePrivateKey, EncodePrivateKey, LineEnding},
+    traits::PublicKeyParts,
+    RsaPrivateKey, RsaPublicKey,
+};
+use serde::{de::DeserializeOwned, Serialize};
+
+/// Size, in bits, of the RSA keys the provider signs tokens with.
+const KEY_SIZE: usize = 2048;
+
+/// The only algorithm the provider signs with.
+pub const ALGORITHM: &str = "RS256";
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("RSA error: {0}")]
+    Rsa(#[from] rsa::Error),
+
+    #[error("PKCS#1 error: {0}")]
+    Pkcs1(#[from] rsa::pkcs1::Error),
+
+    #[error("PKCS#8 error: {0}")]
+    Pkcs8(#[from] rsa::pkcs8::Error),
+
+    #[error("JWT error: {0}")]
+    Jwt(#[from] jsonwebtoken::errors::Error),
+
+    #[error("Unknown key: {0:?}")]
+    UnknownKey(Option<String>),
+}
+
+/// Generates a new RSA private key, PKCS#8 PEM-encoded.
+pub fn generate_private_key() -> Result<String, Error> {
+    let private_key = RsaPrivateKey::new(&mut rand::thread_rng(), KEY_SIZE)?;
+    Ok(private_key.to_pkcs8_pem(LineEnding::LF)?.to_string())
+}
+
+/// The public half of a signing key, as a JSON Web Key (RFC 7517).
+#[derive(Debug, Serialize)]
+pub struct Jwk {
+    kty: &'static str,
+    kid: String,
+    alg: &'static str,
+    #[serde(rename = "use")]
+    use_: &'static str,
+    n: String,
+    e: String,
+}
+
+/// An RSA key the provider signs tokens with.
+pub struct SigningKey {
+    kid: String,
+    encoding_key: EncodingKey,
+    decoding_key: DecodingKey,
+    public_key: RsaPublicKey,
+}
+
+impl SigningKey {
+    pub fn from_pem(kid: String, pem: &str) -> Result<Self, Error> {
+        let private_key = RsaPrivateKey::from_pkcs8_pem(pem)?;
+        let der = private_key.to_pkcs1_der()?;
+        let public_key = private_key.to_public_key();
+        Ok(SigningKey {
+            kid,
+            encoding_key: EncodingKey::from_rsa_der(der.as_bytes()),
+            decoding_key: DecodingKey::from_rsa_raw_components(
+                &public_key.n().to_bytes_be(),
+                &public_key.e().to_bytes_be(),
+            ),
+            public_key,
+        })
+    }
+
+    pub fn kid(&self) -> &str {
+        &self.kid
+    }
+
+    pub fn jwk(&self) -> Jwk {
+        Jwk {
+            kty: "RSA",
+            kid: self.kid.clone(),
+            alg: ALGORITHM,
+            use_: "sig",
+            n: URL_SAFE_NO_PAD.encode(self.public_key.n().to_bytes_be()),
+            e: URL_SAFE_NO_PAD.encode(self.public_key.e().to_bytes_be()),
+        }
+    }
+
+    /// Signs `claims` as a JWS using RS256.
+    pub fn sign<T: Serialize>(&self, claims: &T) -> Result<String, Error> {
+        self.sign_as("JWT", claims)
+    }
+
+    /// Signs `claims` as a JWS using RS256, declaring `typ` as the media type of the token
+    /// (RFC 7515 4.1.9).
+    pub fn sign_as<T: Serialize>(&self, typ: &str, claims: &T) -> Result<String, Error> {
+        let mut header = Header::new(Algorithm::RS256);
+        header.typ = Some(typ.to_string());
+        header.kid = Some(self.kid.clone());
+        Ok(jsonwebtoken::encode(&header, claims, &self.encoding_key)?)
+    }
+
+    /// Checks the signature of `token` and returns its claims, provided they pass `validation`.
+    pub fn verify<T: DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<T, Error> {
+        Ok(jsonwebtoken::decode(token, &self.decoding_key, validation)?.claims)
+    }
+}
@@ -0,0 +1,49 @@
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How many failed attempts are allowed before further attempts are refused.
+const MAX_FAILURES: u32 = 5;
+
+/// How long attempts are refused once the limit is reached, counted from the last failure.
+const LOCKOUT: Duration = Duration::from_secs(300);
+
+struct Failures {
+    count: u32,
+    last: Instant,
+}
+
+/// Counts failed password attempts per username, to slow down guessing. Unknown usernames are
+/// throttled like any other, so that the throttle does not reveal which accounts exist. The
+/// counts live in memory and are lost on restart.
+#[derive(Default)]
+pub struct Throttle(Mutex<HashMap<String, Failures>>);
+
+impl Throttle {
+    /// Whether an attempt for `username` may be made at all.
+    pub fn allows(&self, username: &str) -> bool {
+        let failures = self.0.lock().expect("throttle poisoned");
+        match failures.get(username) {
+            Some(f) => f.count < MAX_FAILURES || f.last.elapsed() >= LOCKOUT,
+            None => true,
+        }
+    }
+
+    pub fn failure(&self, username: &str) {
+        let mut failures = self.0.lock().expect("throttle poisoned");
+        // Forget about usernames nobody tried in a while, so the map does not grow unbounded.
+        failures.retain(|_, f| f.last.elapsed() < LOCKOUT);
+        let entry = failures.entry(username.to_string()).or_insert(Failures {
+            count: 0,
+            last: Instant::now(),
+        });
+        entry.count += 1;
+        entry.last = Instant::now();
+    }
+
+    pub fn success(&self, username: &str) {
+        self.0.lock().expect("throttle poisoned").remove(username);
+    }
+}
@@ -0,0 +1,528 @@
+use std::str::FromStr;
+
+use chrono::Utc;
+
+use entity::{
+    clients::{self, ClientType, GrantType, Scope, TokenEndpointAuthMethod},
+    uuid::Uuid,
+};
+use rocket::{
+    form::Form,
+    http::{Header, Status},
+    request,
+    serde::json::Json,
+    State,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde::Serialize;
+use subtle::ConstantTimeEq;
+
+use crate::{config::Config, rest::clients::parse_basic_auth, App};
+
+use super::{
+    access_token::AccessToken,
+    code,
+    id_token::IdToken,
+    login::{self, Authentication},
+    refresh_token, scope, signing, ErrorResponse, OidcError,
+};
+
+/// The grants the token endpoint knows how to exchange.
+pub const GRANT_TYPES: &[GrantType] = &[
+    GrantType::AuthorizationCode,
+    GrantType::RefreshToken,
+    GrantType::ClientCredentials,
+    GrantType::Password,
+];
+
+/// The grants this server accepts: the password grant is only there when it is switched on.
+pub fn grant_types(config: &Config) -> Vec<GrantType> {
+    GRANT_TYPES
+        .iter()
+        .filter(|grant_type| **grant_type != GrantType::Password || config.password_grant)
+        .cloned()
+        .collect()
+}
+
+/// The ways clients can authenticate to the token endpoint.
+pub const AUTH_METHODS: &[&str] = &["client_secret_basic", "client_secret_post", "none"];
+
+#[derive(FromForm)]
+pub struct TokenPayload {
+    /// The grant being exchanged for tokens. Kept as a raw string so that unknown values can be
+    /// reported as `unsupported_grant_type` rather than rejected by the form parser.
+    grant_type: String,
+
+    /// The authorization code received from the authorization server.
+    code: Option<String>,
+
+    /// Must be identical to the `redirect_uri` included in the authorization request.
+    redirect_uri: Option<String>,
+
+    /// The PKCE code verifier the code challenge of the authorization request was derived from.
+    code_verifier: Option<String>,
+
+    /// The refresh token issued to the client, when refreshing.
+    refresh_token: Option<String>,
+
+    /// The scope of the access token. When refreshing, it must not include any scope value not
+    /// originally granted, and defaults to the original grant. With client credentials, it is
+    /// limited to what the client registered, and defaults to that.
+    scope: Option<String>,
+
+    /// The resource owner's username, with the password grant.
+    username: Option<String>,
+
+    /// The resource owner's password, with the password grant.
+    password: Option<String>,
+
+    /// Client identifier, when the client authenticates with `client_secret_post`.
+    client_id: Option<String>,
+
+    /// Client secret, when the client authenticates with `client_secret_post`.
+    client_secret: Option<String>,
+}
+
+/// Client credentials presented in the `Authorization` header (`client_secret_basic`).
+pub struct BasicCredentials {
+    client_id: String,
+    client_secret: String,
+}
+
+#[rocket::async_trait]
+impl<'r> request::FromRequest<'r> for BasicCredentials {
+    type Error = ();
+
+    async fn from_request(
+        request: &'r request::Request<'_>,
+    ) -> request::Outcome<Self, Self::Error> {
+        let Some(auth) = request.headers().get_one("Authorization") else {
+            return request::Outcome::Forward(Status::Unauthorized);
+        };
+
+        match parse_basic_auth(auth) {
+            Some((client_id, client_secret)) => request::Outcome::Success(BasicCredentials {
+                client_id,
+                client_secret,
+            }),
+            None => request::Outcome::Error((Status::Unauthorized, ())),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct TokenResponse {
+    access_token: String,
+    token_type: &'static str,
+    expires_in: i64,
+    scope: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    id_token: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+}
+
+#[derive(Responder)]
+pub struct TokenResponder {
+    inner: Json<TokenResponse>,
+    cache_control: Header<'static>,
+    pragma: Header<'static>,
+}
+
+impl From<TokenResponse> for TokenResponder {
+    fn from(response: TokenResponse) -> Self {
+        TokenResponder {
+            inner: Json(response),
+            cache_control: Header::new("Cache-Control", "no-store"),
+            pragma: Header::new("Pragma", "no-cache"),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+enum Error {
+    #[error("Database error: {0}")]
+    Db(#[from] sea_orm::DbErr),
+
+    #[error("OIDC error: {0}")]
+    Oidc(#[from] OidcError),
+
+    #[error("Signing error: {0}")]
+    Signing(#[from] signing::Error),
+}
+
+/// A failed token request (RFC 6749 5.2).
+#[derive(Responder)]
+pub enum TokenError {
+    /// Client authentication failed, which comes with a challenge for HTTP Basic, the way clients
+    /// are expected to authenticate.
+    #[response(status = 401)]
+    Unauthorized(Json<ErrorResponse>, Header<'static>),
+    Failed((Status, Json<ErrorResponse>)),
+}
+
+impl From<Error> for TokenError {
+    fn from(err: Error) -> Self {
+        match err {
+            Error::Oidc(e @ OidcError::InvalidClient) => TokenError::Unauthorized(
+                Json((&e).into()),
+                Header::new("WWW-Authenticate", "Basic"),
+            ),
+            Error::Oidc(e) => TokenError::Failed((Status::BadRequest, Json((&e).into()))),
+            e => {
+                tracing::error!("token request failed: {}", e);
+                TokenError::Failed((
+                    Status::InternalServerError,
+                    Json((&OidcError::ServerError).into()),
+                ))
+            }
+        }
+    }
+}
+
+async fn authenticate_client(
+    app: &App,
+    basic: Option<BasicCredentials>,
+    payload: &TokenPayload,
+) -> Result<clients::Model, Error> {
+    let (client_id, client_secret, method) =
+        match (basic, &payload.client_id, &payload.client_secret) {
+            (Some(_), _, Some(_)) => {
+                return Err(
+                    OidcError::InvalidRequest("multiple client authentication methods").into(),
+                )
+            }
+            (Some(basic), _, None) => (
+                basic.client_id,
+                Some(basic.client_secret),
+                TokenEndpointAuthMethod::ClientSecretBasic,
+            ),
+            (None, Some(id), Some(secret)) => (
+                id.clone(),
+                Some(secret.clone()),
+                TokenEndpointAuthMethod::ClientSecretPost,
+            ),
+            // Public clients only identify themselves, the code verifier proves the rest.
+            (None, Some(id), None) => (id.clone(), None, TokenEndpointAuthMethod::None),
+            (None, None, _) => return Err(OidcError::InvalidClient.into()),
+        };
+
+    let uuid = Uuid::from_str(&client_id).map_err(|_| OidcError::InvalidClient)?;
+    let client = clients::Entity::find()
+        .filter(clients::Column::Uuid.eq(uuid))
+        .one(&app.seaorm_pool)
+        .await?;
+
+    // Clients authenticate with the method they registered and no other. Those registered with
+    // `none` have no secret, so they cannot present one.
+    let Some(client) = client.filter(|client| client.token_endpoint_auth_method == method) else {
+        return Err(OidcError::InvalidClient.into());
+    };
+    let authenticated = match (&client.secret, &client_secret) {
+        // In constant time, so that how long the comparison takes tells nothing about the secret.
+        (Some(secret), Some(presented)) => secret.as_bytes().ct_eq(presented.as_bytes()).into(),
+        (None, None) => true,
+        _ => false,
+    };
+    if !authenticated {
+        return Err(OidcError::InvalidClient.into());
+    }
+    Ok(client)
+}
+
+async fn exchange_authorization_code(
+    app: &App,
+    client: &clients::Model,
+    payload: &TokenPayload,
+) -> Result<TokenResponse, Error> {
+    let Some(code) = &payload.code else {
+        return Err(OidcError::InvalidRequest("missing code").into());
+    };
+    let Some(grant) = code::redeem(&app.seaorm_pool, code).await? else {
+        return Err(OidcError::InvalidGrant.into());
+    };
+    if grant.client_id != client.id
+        || payload.redirect_uri.as_deref() != Some(grant.redirect_uri.as_str())
+    {
+        return Err(OidcError::InvalidGrant.into());
+    }
+    match (code::challenge(&grant), &payload.code_verifier) {
+        (Some(challenge), Some(verifier)) if challenge.verify(verifier) => {}
+        // Public clients cannot authenticate otherwise.
+        (None, None) if client.client_type == ClientType::Confidential => {}
+        // A verifier for a code issued without a challenge is refused as well, or an attacker
+        // could get a stolen code accepted by simply dropping the challenge.
+        _ => return Err(OidcError::InvalidGrant.into()),
+    }
+
+    let access_token = AccessToken::new(
+        &app.config.issuer,
+        &grant.subject,
+        &client.uuid.to_string(),
+        &grant.scope.0,
+        app.config.token_lifetime,
+    )
+    .sign(&app.keys.signing_key())?;
+    let id_token = IdToken::new(
+        &app.config.issuer,
+        &grant.subject,
+        &client.uuid.to_string(),
+        app.config.token_lifetime,
+        grant.auth_time,
+    )
+    .nonce(grant.nonce)
+    .access_token(&access_token)
+    .code(code)
+    .sign(&app.keys.signing_key())?;
+
+    let refresh_token = if offers_refresh(client, &grant.scope) {
+        let refresh = refresh_token::Grant {
+            subject: grant.subject,
+            scope: grant.scope.clone(),
+            auth_time: grant.auth_time,
+        };
+        let family = grant.refresh_token_family;
+        let lifetime = app.config.refresh_token_lifetime;
+        Some(refresh_token::issue(&app.seaorm_pool, client, refresh, family, lifetime).await?)
+    } else {
+        None
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: app.config.token_lifetime,
+        scope: grant.scope.into_inner().join(" "),
+        id_token: Some(id_token),
+        refresh_token,
+    })
+}
+
+/// Whether the client gets a refresh token along with its access token: only when it registered
+/// for the refresh token grant and the end user granted it offline access (OIDC Core 11).
+fn offers_refresh(client: &clients::Model, scope: &Scope) -> bool {
+    client.grant_types.0.contains(&GrantType::RefreshToken)
+        && scope.0.iter().any(|value| value == "offline_access")
+}
+
+async fn exchange_refresh_token(
+    app: &App,
+    client: &clients::Model,
+    payload: &TokenPayload,
+) -> Result<TokenResponse, Error> {
+    let Some(token) = &payload.refresh_token else {
+        return Err(OidcError::InvalidRequest("missing refresh_token").into());
+    };
+    let Some(grant) = refresh_token::find(&app.seaorm_pool, token).await? else {
+        return Err(OidcError::InvalidGrant.into());
+    };
+    if grant.client_id != client.id {
+        return Err(OidcError::InvalidGrant.into());
+    }
+
+    let scope = match &payload.scope {
+        Some(requested) => {
+            let requested = scope::parse(requested)?;
+            if !requested
+                .0
+                .iter()
+                .all(|value| grant.scope.0.contains(value))
+            {
+                return Err(OidcError::InvalidScope.into());
+            }
+            requested
+        }
+        None => grant.scope.clone(),
+    };
+    // Only a valid request uses up the token, so that the client can fix its request.
+    if !refresh_token::consume(&app.seaorm_pool, &grant).await? {
+        return Err(OidcError::InvalidGrant.into());
+    }
+
+    let access_token = AccessToken::new(
+        &app.config.issuer,
+        &grant.subject,
+        &client.uuid.to_string(),
+        &scope.0,
+        app.config.token_lifetime,
+    )
+    .sign(&app.keys.signing_key())?;
+    // The ID token of a refresh carries no nonce (OIDC Core 12.2).
+    let id_token = if scope.0.iter().any(|value| value == "openid") {
+        let id_token = IdToken::new(
+            &app.config.issuer,
+            &grant.subject,
+            &client.uuid.to_string(),
+            app.config.token_lifetime,
+            grant.auth_time,
+        )
+        .access_token(&access_token)
+        .sign(&app.keys.signing_key())?;
+        Some(id_token)
+    } else {
+        None
+    };
+
+    // The replacement keeps the scope of the original grant, whatever this access token was
+    // narrowed down to.
+    let refresh = refresh_token::Grant {
+        subject: grant.subject,
+        scope: grant.scope,
+        auth_time: grant.auth_time,
+    };
+    let lifetime = app.config.refresh_token_lifetime;
+    let refresh_token = refresh_token::issue(
+        &app.seaorm_pool,
+        client,
+        refresh,
+        Some(grant.family),
+        lifetime,
+    )
+    .await?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: app.config.token_lifetime,
+        scope: scope.into_inner().join(" "),
+        id_token,
+        refresh_token: Some(refresh_token),
+    })
+}
+
+/// Exchanges the end user's own credentials for tokens (RFC 6749 4.3). This grant is only meant for
+/// legacy clients that cannot be migrated to the authorization code flow yet.
+async fn exchange_password(
+    app: &App,
+    client: &clients::Model,
+    payload: &TokenPayload,
+) -> Result<TokenResponse, Error> {
+    let (Some(username), Some(password)) = (&payload.username, &payload.password) else {
+        return Err(OidcError::InvalidRequest("missing username or password").into());
+    };
+    let scope = match &payload.scope {
+        Some(requested) => scope::restrict(
+            scope::parse(requested)?,
+            &client.scope,
+            app.config.scope_policy,
+        )?,
+        None => client.scope.clone(),
+    };
+
+    let Authentication::Success(user) = login::authenticate(app, username, password).await? else {
+        return Err(OidcError::InvalidGrant.into());
+    };
+
+    // The end user authenticated just now, by handing their password to the client.
+    let auth_time = Utc::now();
+    let subject = user.subject.to_string();
+    let access_token = AccessToken::new(
+        &app.config.issuer,
+        &subject,
+        &client.uuid.to_string(),
+        &scope.0,
+        app.config.token_lifetime,
+    )
+    .sign(&app.keys.signing_key())?;
+    let id_token = if scope.0.iter().any(|value| value == "openid") {
+        let id_token = IdToken::new(
+            &app.config.issuer,
+            &subject,
+            &client.uuid.to_string(),
+            app.config.token_lifetime,
+            auth_time,
+        )
+        .access_token(&access_token)
+        .sign(&app.keys.signing_key())?;
+        Some(id_token)
+    } else {
+        None
+    };
+    let refresh_token = if offers_refresh(client, &scope) {
+        let refresh = refresh_token::Grant {
+            subject,
+            scope: scope.clone(),
+            auth_time,
+        };
+        let lifetime = app.config.refresh_token_lifetime;
+        Some(refresh_token::issue(&app.seaorm_pool, client, refresh, None, lifetime).await?)
+    } else {
+        None
+    };
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: app.config.token_lifetime,
+        scope: scope.into_inner().join(" "),
+        id_token,
+        refresh_token,
+    })
+}
+
+/// Issues an access token on behalf of the client itself (RFC 6749 4.4), for service-to-service
+/// calls. There is no end user, so neither an ID token nor a refresh token is issued.
+async fn exchange_client_credentials(
+    app: &App,
+    client: &clients::Model,
+    payload: &TokenPayload,
+) -> Result<TokenResponse, Error> {
+    // Only confidential clients can prove who they are on their own.
+    if client.client_type != ClientType::Confidential {
+        return Err(OidcError::UnauthorizedClient.into());
+    }
+    let scope = match &payload.scope {
+        Some(requested) => scope::restrict(
+            scope::parse(requested)?,
+            &client.scope,
+            app.config.scope_policy,
+        )?,
+        None => client.scope.clone(),
+    };
+
+    let client_id = client.uuid.to_string();
+    let access_token = AccessToken::new(
+        &app.config.issuer,
+        &client_id,
+        &client_id,
+        &scope.0,
+        app.config.token_lifetime,
+    )
+    .sign(&app.keys.signing_key())?;
+
+    Ok(TokenResponse {
+        access_token,
+        token_type: "Bearer",
+        expires_in: app.config.token_lifetime,
+        scope: scope.into_inner().join(" "),
+        id_token: None,
+        refresh_token: None,
+    })
+}
+
+#[post("/", data = "<payload>")]
+pub async fn token(
+    app: &State<App>,
+    basic: Option<BasicCredentials>,
+    payload: Form<TokenPayload>,
+) -> Result<TokenResponder, TokenError> {
+    let client = authenticate_client(app, basic, &payload).await?;
+
+    let grant_type = GrantType::from_str(&payload.grant_type)
+        .ok()
+        .filter(|grant_type| grant_types(&app.config).contains(grant_type))
+        .ok_or(Error::Oidc(OidcError::UnsupportedGrantType))?;
+    if !client.grant_types.0.contains(&grant_type) {
+        return Err(Error::Oidc(OidcError::UnauthorizedClient).into());
+    }
+
+    let response = match grant_type {
+        GrantType::AuthorizationCode => exchange_authorization_code(app, &client, &payload).await?,
+        GrantType::RefreshToken => exchange_refresh_token(app, &client, &payload).await?,
+        GrantType::ClientCredentials => exchange_client_credentials(app, &client, &payload).await?,
+        GrantType::Password => exchange_password(app, &client, &payload).await?,
+        _ => return Err(Error::Oidc(OidcError::UnsupportedGrantType).into()),
+    };
+
+    Ok(response.into())
+}
@@ -0,0 +1,170 @@
+use std::str::FromStr;
+
+use entity::{users, uuid::Uuid};
+use rocket::{
+    form::Form,
+    http::{Header, Status},
+    request::{self, FromRequest, Request},
+    serde::json::Json,
+    State,
+};
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use serde_json::{Map, Value};
+
+use crate::App;
+
+use super::{access_token::AccessToken, scope, ErrorResponse, OidcError};
+
+/// Every claim the provider knows about `user`. Claims are the columns of the `users` table, save
+/// for the few whose name or format OIDC Core 5.1 defines differently, so that a claim for a
+/// custom scope only needs a column.
+fn available(user: &users::Model) -> Map<String, Value> {
+    let Ok(Value::Object(mut claims)) = serde_json::to_value(user) else {
+        unreachable!("users serialize to an object");
+    };
+    for internal in ["id", "subject", "username", "created_at", "updated_at"] {
+        claims.remove(internal);
+    }
+    claims.insert("sub".to_string(), user.subject.to_string().into());
+    claims.insert(
+        "preferred_username".to_string(),
+        user.username.clone().into(),
+    );
+    claims.insert("updated_at".to_string(), user.updated_at.timestamp().into());
+    claims
+}
+
+/// The claims about `user` that the granted `scope` values give access to.
+pub fn release<'a>(
+    user: &users::Model,
+    scope: impl Iterator<Item = &'a str>,
+) -> Map<String, Value> {
+    let available = available(user);
+    let mut released = Map::new();
+    for definition in scope.filter_map(scope::definition) {
+        for claim in definition.claims {
+            match available.get(*claim) {
+                Some(Value::Null) | None => {}
+                Some(value) => {
+                    released.insert(claim.to_string(), value.clone());
+                }
+            }
+        }
+    }
+    released
+}
+
+/// An access token presented in the `Authorization` header (RFC 6750 2.1).
+pub struct BearerToken(String);
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for BearerToken {
+    type Error = ();
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, Self::Error> {
+        let token = request
+            .headers()
+            .get_one("Authorization")
+            .and_then(|auth| auth.strip_prefix("Bearer "));
+        match token {
+            Some(token) => request::Outcome::Success(BearerToken(token.trim().to_string())),
+            None => request::Outcome::Forward(Status::Unauthorized),
+        }
+    }
+}
+
+/// An access token sent in the request body instead (RFC 6750 2.2).
+#[derive(FromForm)]
+pub struct UserInfoPayload {
+    access_token: String,
+}
+
+/// A failed UserInfo request, along with the challenge telling the client what went wrong
+/// (RFC 6750 3).
+#[derive(Responder)]
+pub enum Challenge {
+    /// No token was presented at all, in which case the challenge carries no error code.
+    #[response(status = 401)]
+    Missing((), Header<'static>),
+    Failed((Status, Json<ErrorResponse>), Header<'static>),
+}
+
+impl Challenge {
+    fn missing() -> Self {
+        Challenge::Missing((), Header::new("WWW-Authenticate", "Bearer"))
+    }
+}
+
+impl From<OidcError> for Challenge {
+    fn from(err: OidcError) -> Self {
+        let status = match err {
+            OidcError::InvalidRequest(_) => Status::BadRequest,
+            OidcError::InsufficientScope => Status::Forbidden,
+            OidcError::InvalidToken => Status::Unauthorized,
+            _ => Status::InternalServerError,
+        };
+        let challenge = format!(
+            "Bearer error=\"{}\", error_description=\"{}\"",
+            err.code(),
+            err
+        );
+        Challenge::Failed(
+            (status, Json((&err).into())),
+            Header::new("WWW-Authenticate", challenge),
+        )
+    }
+}
+
+async fn userinfo(app: &App, token: Option<String>) -> Result<Json<Map<String, Value>>, Challenge> {
+    let Some(token) = token else {
+        return Err(Challenge::missing());
+    };
+    let token = AccessToken::verify(&app.keys, &app.config.issuer, &token).map_err(|e| {
+        tracing::debug!("rejected access token: {}", e);
+        OidcError::InvalidToken
+    })?;
+    if !token.scope().any(|value| value == "openid") {
+        return Err(OidcError::InsufficientScope.into());
+    }
+
+    let subject = Uuid::from_str(token.subject()).map_err(|_| OidcError::InvalidToken)?;
+    let user = users::Entity::find()
+        .filter(users::Column::Subject.eq(subject))
+        .one(&app.seaorm_pool)
+        .await
+        .map_err(|e| {
+            tracing::error!("userinfo request failed: {}", e);
+            OidcError::ServerError
+        })?;
+    // The end user was deleted after the token was issued.
+    let Some(user) = user else {
+        return Err(OidcError::InvalidToken.into());
+    };
+
+    Ok(Json(release(&user, token.scope())))
+}
+
+#[get("/")]
+pub async fn userinfo_get(
+    app: &State<App>,
+    bearer: Option<BearerToken>,
+) -> Result<Json<Map<String, Value>>, Challenge> {
+    userinfo(app, bearer.map(|bearer| bearer.0)).await
+}
+
+#[post("/", data = "<payload>")]
+pub async fn userinfo_post(
+    app: &State<App>,
+    bearer: Option<BearerToken>,
+    payload: Option<Form<UserInfoPayload>>,
+) -> Result<Json<Map<String, Value>>, Challenge> {
+    let token = match (bearer, payload) {
+        (Some(_), Some(_)) => {
+            return Err(OidcError::InvalidRequest("multiple access tokens").into());
+        }
+        (Some(bearer), None) => Some(bearer.0),
+        (None, Some(payload)) => Some(payload.into_inner().access_token),
+        (None, None) => None,
+    };
+    userinfo(app, token).await
+}
@@ -1,7 +1,9 @@
 use rocket::{http::Status, serde::json::Json, State};
 
 use entity::{
-    clients::{self, GrantTypes, RedirectUris, ResponseTypes, Scope},
+    clients::{
+        self, ClientType, GrantTypes, RedirectUris, ResponseTypes, Scope, TokenEndpointAuthMethod,
+    },
     uuid::Uuid,
 };
 use sea_orm::{ActiveModelTrait, EntityTrait, Set};
@@ -11,6 +13,7 @@ use crate::App;
 
 mod rotate_secret;
 
+pub(crate) use rotate_secret::parse_basic_auth;
 pub use rotate_secret::rotate_client_secret;
 
 #[derive(Debug, thiserror::Error)]
@@ -20,6 +23,9 @@ pub enum ClientError {
 
     #[error("Not authorized")]
     NotAuthorized,
+
+    #[error("Invalid client metadata: {0}")]
+    InvalidMetadata(&'static str),
 }
 
 impl ClientError {
@@ -35,6 +41,7 @@ impl From<ClientError> for (Status, String) {
         match err {
             ClientError::DbErr(e) => (Status::InternalServerError, e.to_string()),
             ClientError::NotAuthorized => (Status::Unauthorized, "Unauthorized".to_string()),
+            e @ ClientError::InvalidMetadata(_) => (Status::BadRequest, e.to_string()),
         }
     }
 }
@@ -47,6 +54,23 @@ pub struct CreateClientPayload {
     grant_types: GrantTypes,
     response_types: ResponseTypes,
     scope: Scope,
+
+    /// First-party clients, whose end users are not asked for consent. Anyone can register a
+    /// client here, so this is refused: trusted clients are set up directly in the database.
+    #[serde(default)]
+    trusted: bool,
+
+    /// Whether authorization requests must use PKCE (RFC 7636).
+    #[serde(default)]
+    require_pkce: bool,
+
+    /// Public clients (single-page and native apps) get no secret. Clients are confidential
+    /// unless stated otherwise.
+    #[serde(default)]
+    client_type: ClientType,
+
+    /// Defaults to `client_secret_basic` for confidential clients and to `none` for public ones.
+    token_endpoint_auth_method: Option<TokenEndpointAuthMethod>,
 }
 
 pub fn generate_secret(size: usize) -> String {
@@ -74,15 +98,39 @@ pub async fn create_client(
     app: &State<App>,
     payload: Json<CreateClientPayload>,
 ) -> Result<Json<clients::Model>, (Status, String)> {
+    if payload.trusted {
+        return Err(ClientError::InvalidMetadata(
+            "trusted clients cannot be registered through this endpoint",
+        )
+        .into());
+    }
+    let public = payload.client_type == ClientType::Public;
+    let auth_method = payload.token_endpoint_auth_method.unwrap_or(if public {
+        TokenEndpointAuthMethod::None
+    } else {
+        TokenEndpointAuthMethod::ClientSecretBasic
+    });
+    // A public client has no secret to authenticate with, a confidential one must use it.
+    if public != (auth_method == TokenEndpointAuthMethod::None) {
+        return Err(ClientError::InvalidMetadata(
+            "token_endpoint_auth_method must be none for public clients and only for them",
+        )
+        .into());
+    }
+
     let client = clients::ActiveModel {
         name: Set(payload.name.clone()),
         uuid: Set(Uuid::default()),
-        secret: Set(generate_secret(64)),
+        secret: Set((!public).then(|| generate_secret(64))),
         description: Set(payload.description.clone()),
         redirect_uris: Set(payload.redirect_uris.clone()),
         grant_types: Set(payload.grant_types.clone()),
         response_types: Set(payload.response_types.clone()),
         scope: Set(payload.scope.clone()),
+        trusted: Set(false),
+        require_pkce: Set(payload.require_pkce),
+        client_type: Set(payload.client_type),
+        token_endpoint_auth_method: Set(auth_method),
         ..Default::default()
     };
 
@@ -41,7 +41,7 @@ impl SecretRotator {
 
         let mut client: clients::ActiveModel = client.into();
         let new_secret = generate_secret(64);
-        client.secret = Set(new_secret.clone());
+        client.secret = Set(Some(new_secret.clone()));
         client.update(&app.seaorm_pool).await?;
 
         Ok(new_secret)
@@ -61,7 +61,7 @@ impl SecretRotator {
     }
 }
 
-fn parse_basic_auth(header: &str) -> Option<(String, String)> {
+pub(crate) fn parse_basic_auth(header: &str) -> Option<(String, String)> {
     let (auth_type, auth) = header.split_once(' ')?;
     if auth_type != "Basic" {
         return None;
@@ -0,0 +1,82 @@
+use chrono::Utc;
+use rocket::{http::Status, serde::json::Json, State};
+
+use entity::{
+    users::{self, Address},
+    uuid::Uuid,
+};
+use sea_orm::{ActiveModelTrait, Set, SqlErr};
+use serde::Deserialize;
+
+use crate::{password, App};
+
+#[derive(Deserialize)]
+pub struct CreateUserPayload {
+    username: String,
+    password: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+    name: Option<String>,
+    given_name: Option<String>,
+    family_name: Option<String>,
+    middle_name: Option<String>,
+    nickname: Option<String>,
+    profile: Option<String>,
+    picture: Option<String>,
+    website: Option<String>,
+    gender: Option<String>,
+    birthdate: Option<String>,
+    zoneinfo: Option<String>,
+    locale: Option<String>,
+    phone_number: Option<String>,
+    #[serde(default)]
+    phone_number_verified: bool,
+    address: Option<Address>,
+}
+
+#[post("/", data = "<payload>")]
+pub async fn create_user(
+    app: &State<App>,
+    payload: Json<CreateUserPayload>,
+) -> Result<Json<users::Model>, (Status, String)> {
+    let password_hash = password::hash(&payload.password)
+        .map_err(|e| (Status::InternalServerError, e.to_string()))?;
+
+    let user = users::ActiveModel {
+        subject: Set(Uuid::default()),
+        username: Set(payload.username.clone()),
+        email: Set(payload.email.clone()),
+        email_verified: Set(payload.email_verified),
+        name: Set(payload.name.clone()),
+        given_name: Set(payload.given_name.clone()),
+        family_name: Set(payload.family_name.clone()),
+        middle_name: Set(payload.middle_name.clone()),
+        nickname: Set(payload.nickname.clone()),
+        profile: Set(payload.profile.clone()),
+        picture: Set(payload.picture.clone()),
+        website: Set(payload.website.clone()),
+        gender: Set(payload.gender.clone()),
+        birthdate: Set(payload.birthdate.clone()),
+        zoneinfo: Set(payload.zoneinfo.clone()),
+        locale: Set(payload.locale.clone()),
+        phone_number: Set(payload.phone_number.clone()),
+        phone_number_verified: Set(payload.phone_number_verified),
+        address: Set(payload.address.clone()),
+        password_hash: Set(password_hash),
+        created_at: Set(Utc::now()),
+        updated_at: Set(Utc::now()),
+        ..Default::default()
+    };
+
+    let user = user.insert(&app.seaorm_pool).await;
+    match user {
+        Ok(user) => Ok(Json(user)),
+        Err(e) => match e.sql_err() {
+            Some(SqlErr::UniqueConstraintViolation(_)) => {
+                Err((Status::Conflict, "Username already taken".to_string()))
+            }
+            _ => Err((Status::InternalServerError, e.to_string())),
+        },
+    }
+}